@@ -0,0 +1,19 @@
+pub mod solver;
+
+pub use solver::ant_colony::start::StartStrategy;
+pub use solver::availability::DayAvailability;
+pub use solver::blocks::Block;
+pub use solver::checkpoint::Checkpoint;
+pub use solver::config::SolverConfig;
+pub use solver::error::SolverError;
+pub use solver::horizon::Horizon;
+pub use solver::instance::Instance;
+pub use solver::objective::ObjectiveMode;
+pub use solver::pheromones::PheromoneMap;
+pub use solver::policy::{PriorityLevel, PriorityPolicy};
+pub use solver::resources::ResourceConfig;
+pub use solver::rooms::Room;
+pub use solver::solution::{Assignment, Solution, StopReason};
+pub use solver::surgery::Surgery;
+pub use solver::week::placement::PlacementStrategy;
+pub use solver::Solver;
@@ -1,15 +1,20 @@
-mod solver;
-
-use crate::solver::week::Week;
-use solver::surgery::{DaysWaiting, Priority};
-use solver::Solver;
-use std::collections::HashMap;
+use aco_surgeries::solver::{
+    availability, blocks, breakdown, evaluation, pheromones, rooms, simulation, solution,
+    validation,
+};
+use aco_surgeries::{
+    Checkpoint, Horizon, Instance, ObjectiveMode, PlacementStrategy, PriorityPolicy,
+    ResourceConfig, Solution, Solver, SolverConfig, SolverError, StartStrategy, StopReason,
+};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use structopt::StructOpt;
-
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "Ant Colony Optimization for surgery scheduling",
@@ -24,7 +29,8 @@ struct Opt {
     #[structopt(
         short = "f",
         long = "file",
-        default_value = "./sample_data/Indefinidas - i1.csv"
+        default_value = "./sample_data/Indefinidas - i1.csv",
+        global = true
     )]
     instance_file: String,
 
@@ -76,80 +82,358 @@ struct Opt {
     #[structopt(short = "T", long = "target", default_value = "0.0")]
     target: f64,
 
+    /// Seconds an execution may run, checked at the end of each round. Combines with the other
+    /// stopping criteria, whichever comes first.
+    #[structopt(long = "time_limit")]
+    time_limit: Option<f64>,
+
+    /// Seed of the first execution, the next ones use the following numbers. Random when absent,
+    /// the seed of each execution is printed so it can be replayed with `-N 1`.
+    #[structopt(long = "seed")]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with max waiting days and penalties of each priority level.
+    #[structopt(short = "p", long = "policy", global = true)]
+    policy_file: Option<String>,
+
+    /// TOML or JSON file with slots per day, cleaning time and surgeons daily and weekly limits.
+    #[structopt(long = "resources", global = true)]
+    resources_file: Option<String>,
+
+    /// TOML or JSON file with surgeons calendars: allowed slot windows and limits per day.
+    #[structopt(long = "availability", global = true)]
+    availability_file: Option<String>,
+
+    /// TOML or JSON file with the room catalog: supported specialities and closed days of each
+    /// room. When given, replaces the number of surgery rooms.
+    #[structopt(long = "room_catalog", global = true)]
+    room_catalog_file: Option<String>,
+
+    /// TOML or JSON file with the master surgical schedule: the speciality of each room on each
+    /// day of the week. When given, surgeries are only placed on these blocks.
+    #[structopt(long = "blocks", global = true)]
+    blocks_file: Option<String>,
+
     /// Intesify probability to choose next surgery
     #[structopt(short = "i", long = "intensify_probability", default_value = "0.0")]
     intensify_probability: f64,
+
+    /// TOML or JSON file with the planning horizon: number of weeks, working days per week, start
+    /// date and holidays.
+    #[structopt(long = "horizon", global = true)]
+    horizon_file: Option<String>,
+
+    /// Number of weeks to plan, surgeries that don't fit on them are left unscheduled.
+    #[structopt(short = "w", long = "weeks", global = true)]
+    weeks: Option<usize>,
+
+    /// Weeks optimised by the colony: first_week, total or weighted[:<decay>].
+    #[structopt(long = "objective", default_value = "first_week", global = true)]
+    objective: ObjectiveMode,
+
+    /// Solution csv of a previous run to start from, its surgery order gets extra pheromones.
+    #[structopt(long = "warm_start", global = true)]
+    warm_start_file: Option<String>,
+
+    /// Pheromones added to the edges of the warm start schedule, ants start with 1 on every edge.
+    #[structopt(long = "warm_start_boost", default_value = "1.0", global = true)]
+    warm_start_boost: f64,
+
+    /// Pheromones file, as written by `--save_pheromones`, to start the colony from.
+    #[structopt(long = "load_pheromones", global = true)]
+    load_pheromones_file: Option<String>,
+
+    /// File where the final pheromones of the best execution are saved, one `from;to;pheromone`
+    /// line per pair of surgery ids.
+    #[structopt(long = "save_pheromones", global = true)]
+    save_pheromones_file: Option<String>,
+
+    /// Also save the pheromones every this many rounds, 0 to save only at the end.
+    #[structopt(long = "pheromones_interval", default_value = "0", global = true)]
+    pheromones_interval: u32,
+
+    /// JSON file where the state of the current execution is saved every `--checkpoint_interval`
+    /// rounds: round, best schedule, pheromones and random number generator seed.
+    #[structopt(long = "checkpoint")]
+    checkpoint_file: Option<String>,
+
+    /// Rounds between checkpoints.
+    #[structopt(long = "checkpoint_interval", default_value = "100")]
+    checkpoint_interval: u32,
+
+    /// Checkpoint file to resume the first execution from, the instance and parameters must be the
+    /// same of the interrupted run.
+    #[structopt(long = "resume")]
+    resume_file: Option<String>,
+
+    /// Where surgeries go on a room day: append, first_fit or best_fit over idle gaps.
+    #[structopt(long = "placement", default_value = "append", global = true)]
+    placement: PlacementStrategy,
+
+    /// How each ant picks its first surgery: highest_priority, pheromone, most_overdue or uniform.
+    #[structopt(long = "start", default_value = "highest_priority", global = true)]
+    start: StartStrategy,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Lists every problem of the instance file with its line number, without running any ant.
+    Validate,
+    /// Checks a solution csv of the instance against every rule of the problem and lists the
+    /// ones it breaks, without running any ant.
+    Evaluate {
+        /// Solution csv, as written by the solver or like the `sample_data` ones.
+        #[structopt(long = "solution")]
+        solution_file: String,
+    },
+    /// Re-plans the waiting list every week, performing the first week of each schedule, and
+    /// reports deadline violations, room utilisation and backlog of every week.
+    Simulate {
+        /// Csv file with the instance columns preceded by the week each surgery arrives.
+        #[structopt(long = "arrivals")]
+        arrivals_file: Option<String>,
+
+        /// Number of weeks to simulate.
+        #[structopt(long = "sim_weeks", default_value = "4")]
+        sim_weeks: usize,
+    },
 }
 
 fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), SolverError> {
     let opt = Opt::from_args();
     println!("{:?}", opt);
 
     let n_executions = opt.n_executions;
     let instance_file = &opt.instance_file;
-    let max_rounds = opt.max_rounds;
-    let max_rounds_improv = opt.max_rounds_improv;
-    let elitism_factor = opt.elitism_factor;
-    let deposit = opt.deposit;
-    let evaporation = opt.evaporation;
-    let rooms = opt.rooms;
-    let alpha = opt.alpha;
-    let beta = opt.beta;
     let ants_count = opt.ants_count;
     let threads_count = opt.threads_count;
     let should_save_durations = opt.should_save_durations;
-    let target = opt.target;
-    let intensify_probability = opt.intensify_probability;
-
-    let max_days_waiting = [(1, 3), (2, 15), (3, 60), (4, 365)]
-        .iter()
-        .cloned()
-        .collect::<HashMap<Priority, DaysWaiting>>();
-    let priority_penalties = [(1, 90), (2, 20), (3, 5), (4, 1)]
-        .iter()
-        .cloned()
-        .collect::<HashMap<Priority, u32>>();
+
+    let priority_policy = match &opt.policy_file {
+        Some(policy_file) => PriorityPolicy::from_file(policy_file)?,
+        None => PriorityPolicy::default(),
+    };
+
+    let mut resources = match &opt.resources_file {
+        Some(resources_file) => ResourceConfig::from_file(resources_file)?,
+        None => ResourceConfig::default(),
+    };
+    if let Some(availability_file) = &opt.availability_file {
+        resources.availability = availability::from_file(availability_file)?;
+    }
+    if let Some(room_catalog_file) = &opt.room_catalog_file {
+        resources.rooms = rooms::from_file(room_catalog_file)?;
+    }
+    if let Some(blocks_file) = &opt.blocks_file {
+        resources.blocks = blocks::from_file(blocks_file)?;
+    }
+
+    let mut horizon = match &opt.horizon_file {
+        Some(horizon_file) => Horizon::from_file(horizon_file)?,
+        None => Horizon::default(),
+    };
+    if opt.weeks.is_some() {
+        horizon.weeks = opt.weeks;
+    }
+
+    let mut initial_pheromones = match &opt.load_pheromones_file {
+        Some(pheromones_file) => pheromones::from_csv(pheromones_file)?,
+        None => Default::default(),
+    };
+    if let Some(warm_start_file) = &opt.warm_start_file {
+        pheromones::add_assignments(
+            &mut initial_pheromones,
+            &solution::assignments_from_csv(warm_start_file)?,
+            opt.warm_start_boost,
+        );
+    }
+
+    let mut config = SolverConfig::new()
+        .priority_policy(priority_policy)
+        .resources(resources)
+        .horizon(horizon)
+        .threads_count(threads_count)
+        .ants_count(ants_count)
+        .rooms_count(opt.rooms)
+        .alpha(opt.alpha)
+        .beta(opt.beta)
+        .elitism_factor(opt.elitism_factor)
+        .pheromone_deposit_rate(opt.deposit)
+        .pheromone_evaporation_rate(opt.evaporation)
+        .max_rounds_count(opt.max_rounds)
+        .max_rounds_without_improvement(opt.max_rounds_improv)
+        .target(opt.target)
+        .intensify_probability(opt.intensify_probability)
+        .placement_strategy(opt.placement)
+        .start_strategy(opt.start)
+        .objective_mode(opt.objective)
+        .initial_pheromones(initial_pheromones);
+    if let Some(save_pheromones_file) = &opt.save_pheromones_file {
+        config = config.save_pheromones(save_pheromones_file, opt.pheromones_interval);
+    }
+    if let Some(time_limit) = opt.time_limit {
+        let time_limit = Duration::try_from_secs_f64(time_limit).map_err(|error| {
+            SolverError::Config(format!("time limit of {} seconds, {}", time_limit, error))
+        })?;
+        config = config.time_limit(time_limit);
+    }
+    if let Some(checkpoint_file) = &opt.checkpoint_file {
+        config = config.checkpoint(checkpoint_file, opt.checkpoint_interval);
+    }
+
+    if let Some(Command::Simulate {
+        arrivals_file,
+        sim_weeks,
+    }) = &opt.command
+    {
+        let arrivals = match arrivals_file {
+            Some(arrivals_file) => simulation::arrivals_from_csv(arrivals_file)?,
+            None => Vec::new(),
+        };
+        let instance = Instance::from_csv(instance_file)?;
+        let seed = opt.seed.unwrap_or_else(rand::random);
+        println!("Seed: {}", seed);
+        let reports =
+            simulation::simulate(&instance, &arrivals, *sim_weeks, &config.clone().seed(seed))?;
+        for report in reports {
+            println!(
+                "Week: {}; Arrivals: {}; Performed: {}; Backlog: {}; Deadline violations: {:?}; Utilisation: {:.1}%; Objective: {}",
+                report.week,
+                report.arrivals,
+                report.performed,
+                report.backlog,
+                report.deadline_violations,
+                100.0 * report.utilisation,
+                report.objective
+            );
+        }
+
+        return Ok(());
+    }
+
+    if let Some(Command::Evaluate { solution_file }) = &opt.command {
+        let instance = Instance::from_csv(instance_file)?;
+        let assignments = solution::assignments_from_csv(solution_file)?;
+        let report = evaluation::evaluate(&instance, &assignments, &config);
+        println!("{}", report);
+        println!(
+            "{}",
+            breakdown::breakdown(&instance, &assignments, &config)?
+        );
+        if !report.is_feasible() {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    if let Some(Command::Validate) = opt.command {
+        let report = validation::validate_csv(instance_file, &config)?;
+        println!("{}", report);
+        if !report.is_valid() {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
 
     println!(
         "Running with {} ants on {} threads",
         ants_count, threads_count
     );
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let handler_stop_flag = stop_flag.clone();
+    ctrlc::set_handler(move || {
+        // A second Ctrl-C gives up on the schedule found so far
+        if handler_stop_flag.swap(true, Ordering::Relaxed) {
+            process::exit(130);
+        }
+        eprintln!("Stopping after the current round, press Ctrl-C again to abort");
+    })
+    .map_err(|error| SolverError::ThreadFailure(error.to_string()))?;
+    let config = config.stop_flag(stop_flag);
+
     let mut best_result = f64::INFINITY;
-    let mut best_scheduling = Vec::new();
+    let mut best_solution: Option<Solution> = None;
+    let mut stop_reasons = BTreeMap::new();
 
     let (mut results, mut durations) = (
         Vec::with_capacity(n_executions),
         Vec::with_capacity(n_executions),
     );
+    let instance = Instance::from_csv(instance_file)?;
+    let mut resume = match &opt.resume_file {
+        Some(resume_file) => Some(Checkpoint::from_file(resume_file)?),
+        None => None,
+    };
+    // A resumed execution is the first one, so the following ones go on from its seed
+    let seed = opt
+        .seed
+        .or_else(|| resume.as_ref().map(|checkpoint| checkpoint.master_seed))
+        .unwrap_or_else(rand::random);
     for run in 1..=n_executions {
-        let (result, round, schedule, elapsed_time) = Solver::solve(
-            instance_file,
-            threads_count,
-            ants_count,
-            rooms,
-            max_days_waiting.clone(),
-            priority_penalties.clone(),
-            alpha,
-            beta,
-            elitism_factor,
-            deposit,
-            evaporation,
-            max_rounds,
-            max_rounds_improv,
-            target,
-            intensify_probability,
-        );
-        if result < best_result {
-            best_result = result;
-            best_scheduling = schedule;
+        let run_seed = seed.wrapping_add(run as u64 - 1);
+        let run_config = config.clone().seed(run_seed);
+        // A resumed execution carries on with the state saved on its checkpoint
+        let (solution, used_seed) = match resume.take() {
+            Some(checkpoint) => {
+                let master_seed = checkpoint.master_seed;
+                println!(
+                    "Resuming from round {} of the execution with seed {}",
+                    checkpoint.round, master_seed
+                );
+                (
+                    Solver::solve(&instance, &run_config.resume_from(checkpoint))?,
+                    master_seed,
+                )
+            }
+            None => (Solver::solve(&instance, &run_config)?, run_seed),
+        };
+        // Interrupted before its first round, so there is nothing to report
+        if solution.stop_reason == StopReason::Interrupted && solution.best_round == 0 {
+            break;
         }
         println!(
-            "Run: {}; Best objective function result: {}; Round: {}; Elapsed time: {:#?}",
-            run, result, round, elapsed_time
+            "Run: {}; Best objective function result: {}; First week: {}; Total horizon: {}; Round: {}; Elapsed time: {:#?}; Stop reason: {}; Seed: {}",
+            run,
+            solution.objective,
+            solution.first_week_objective,
+            solution.total_objective,
+            solution.best_round,
+            solution.elapsed_time,
+            solution.stop_reason,
+            used_seed
         );
-        results.push(result);
-        durations.push(elapsed_time)
+        let stop_reason = solution.stop_reason;
+        *stop_reasons.entry(stop_reason).or_insert(0) += 1;
+        results.push(solution.objective);
+        durations.push(solution.elapsed_time);
+        if solution.objective < best_result {
+            best_result = solution.objective;
+            best_solution = Some(solution);
+        }
+        if stop_reason == StopReason::Interrupted {
+            println!("Interrupted, skipping the remaining executions");
+            break;
+        }
+    }
+    if results.is_empty() {
+        println!("Interrupted before any round was completed");
+        return Ok(());
     }
+
+    // Executions completed before an interruption
+    let n_executions = results.len();
     results.sort_by(|a, b| a.partial_cmp(b).unwrap());
     let minimum_result = results[0];
     let median_result = (results[(n_executions - 1) / 2] + results[n_executions / 2]) / 2.0;
@@ -157,6 +441,8 @@ fn main() {
 
     let results_mean = results.iter().sum::<f64>() / (n_executions as f64);
     let durations_mean = durations.iter().sum::<Duration>() / (n_executions as u32);
+    // A single execution has no deviation
+    let degrees_of_freedom = (n_executions as f64 - 1.0).max(1.0);
 
     println!(
         "Minimum Result: {}; Median: {}; Maximum Result: {};\nMean Objective Function: {} ± {}; Mean Elapsed Time: {:#?} ± {:#?}s;",
@@ -167,7 +453,7 @@ fn main() {
         (results
             .iter()
             .fold(0.0, |sum, &value| sum + (value - results_mean).powi(2))
-            / (n_executions as f64 - 1.0))
+            / degrees_of_freedom)
             .sqrt(),
         durations_mean,
         (durations
@@ -175,14 +461,32 @@ fn main() {
             .map(|duration| duration.as_secs_f64())
             .fold(0.0, |sum, value| sum
                 + (value - durations_mean.as_secs_f64()).powi(2))
-            / (n_executions as f64 - 1.0))
+            / degrees_of_freedom)
             .sqrt()
     );
+    println!(
+        "Stop reasons: {}",
+        stop_reasons
+            .iter()
+            .map(|(stop_reason, count)| format!("{}: {}", stop_reason, count))
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
 
     if should_save_durations {
-        save_durations(instance_file, durations, ants_count, threads_count);
+        save_durations(instance_file, durations, ants_count, threads_count)?;
     }
-    schedule_to_csv(instance_file, best_scheduling);
+    if let Some(solution) = best_solution {
+        schedule_to_csv(instance_file, &solution)?;
+        let breakdown = breakdown::breakdown(&instance, &solution.assignments, &config)?;
+        println!("{}", breakdown);
+        breakdown_to_csv(instance_file, &breakdown)?;
+        if let Some(save_pheromones_file) = &opt.save_pheromones_file {
+            pheromones::save(&solution.pheromones, save_pheromones_file)?;
+        }
+    }
+
+    Ok(())
 }
 
 fn save_durations(
@@ -190,7 +494,7 @@ fn save_durations(
     durations: Vec<Duration>,
     ants_count: usize,
     threads_count: usize,
-) {
+) -> Result<(), SolverError> {
     let name = instance_name.split(".csv").next().unwrap();
     let solution_name = format!(
         "{}_durations_{}_ants_{}_threads.dat",
@@ -199,40 +503,29 @@ fn save_durations(
     let mut file = OpenOptions::new()
         .append(true)
         .create(true)
-        .open(solution_name)
-        .expect("Unable to create dat file");
-    durations.into_iter().for_each(|duration| {
-        write!(file, "{:#?}\n", duration.as_secs_f64()).expect("Failed to write duration");
-    })
+        .open(solution_name)?;
+    for duration in durations {
+        writeln!(file, "{:#?}", duration.as_secs_f64())?;
+    }
+
+    Ok(())
 }
 
-fn schedule_to_csv(instance_name: &str, schedule: Vec<(Week, f64)>) {
+fn schedule_to_csv(instance_name: &str, solution: &Solution) -> Result<(), SolverError> {
     let name = instance_name.split(".csv").next().unwrap();
     let solution_name = format!("{}_sol.csv", name);
-    let mut file = File::create(solution_name).expect("Unable to create csv file");
-    write!(file, "Cirurgia (c);Sala (r);Dia (d);Horário (t)\n").expect("Failed to write header");
-
-    let mut results = Vec::new();
-
-    for (week_index, (week, _)) in schedule.into_iter().enumerate() {
-        for (day_index, day) in week.days().iter().enumerate() {
-            for (room_index, room) in day.rooms().iter().enumerate() {
-                for (surgery, (schedule, _)) in
-                    room.surgeries().iter().zip(room.scheduled_surgeons())
-                {
-                    results.push((
-                        surgery.id,
-                        room_index + 1,
-                        (day_index + 1) * (week_index + 1),
-                        schedule.start,
-                    ));
-                }
-            }
-        }
-    }
+    solution.write_csv(File::create(solution_name)?)?;
 
-    results.sort_by(|element1, element2| element1.0.cmp(&element2.0));
-    for row in results {
-        write!(file, "{};{};{};{}\n", row.0, row.1, row.2, row.3,).expect("Failed to write row");
-    }
+    Ok(())
+}
+
+fn breakdown_to_csv(
+    instance_name: &str,
+    breakdown: &breakdown::ObjectiveBreakdown,
+) -> Result<(), SolverError> {
+    let name = instance_name.split(".csv").next().unwrap();
+    let breakdown_name = format!("{}_breakdown.csv", name);
+    breakdown.write_csv(File::create(breakdown_name)?)?;
+
+    Ok(())
 }
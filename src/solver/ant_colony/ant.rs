@@ -1,154 +1,265 @@
 use crossbeam::channel::{Receiver, Sender};
 use rand::prelude::SliceRandom;
 use rand::rngs::SmallRng;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::sync::{Arc, Weak};
 
+use super::pheromone_matrix::PheromoneMatrix;
+use super::start::StartStrategy;
+use super::surgeries_bin::SurgeriesBin;
+use crate::solver::error::SolverError;
+use crate::solver::horizon::Horizon;
+use crate::solver::objective::{self, ObjectiveMode};
+use crate::solver::policy::PriorityPolicy;
+use crate::solver::resources::ResourceConfig;
 use crate::solver::surgeon::SurgeonID;
-use crate::solver::surgery::{DaysWaiting, Priority, Surgery};
+use crate::solver::surgery::Surgery;
+use crate::solver::week::placement::PlacementStrategy;
 use crate::solver::week::Week;
 use rand::{Rng, SeedableRng};
 
 pub struct AntFindSolutionData {
-    pub pheromones: Weak<HashMap<(Surgery, Surgery), f64>>,
-    pub round_number: u32,
+    pub pheromones: Weak<PheromoneMatrix>,
+    /// Seeds the ant random number generator, so a round can be replayed.
+    pub seed: u64,
 }
 
 pub struct AntSolution {
+    /// Value the colony optimises, according to the objective mode.
     pub objective_function_result: f64,
+    pub first_week_objective: f64,
+    pub total_objective: f64,
     pub all_weeks_results: Vec<(Week, f64)>,
-    pub followed_path: Vec<(Surgery, Surgery)>,
+    /// Edges between the surgeries of the tour, as indices of the pheromone matrix.
+    pub followed_path: Vec<(usize, usize)>,
 }
 
 pub struct Ant {
     alpha: f64,
     beta: f64,
-    pheromone_evaporation_rate: f64,
     rooms_count: usize,
-    surgeries_bin: HashSet<Surgery>,
+    resources: Arc<ResourceConfig>,
+    surgeries_bin: SurgeriesBin,
     surgeons_ids: Arc<Vec<SurgeonID>>,
-    max_days_waiting: Arc<HashMap<Priority, DaysWaiting>>,
-    priority_penalties: Arc<HashMap<Priority, u32>>,
+    priority_policy: Arc<PriorityPolicy>,
     random_number_generator: SmallRng,
     intensify_probability: f64,
+    placement_strategy: PlacementStrategy,
+    start_strategy: StartStrategy,
+    horizon: Arc<Horizon>,
+    objective_mode: ObjectiveMode,
     receive_work: Receiver<Option<AntFindSolutionData>>,
-    send_solution: Sender<AntSolution>,
+    send_solution: Sender<Result<AntSolution, SolverError>>,
 }
 
 impl Ant {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         alpha: f64,
         beta: f64,
-        pheromone_evaporation_rate: f64,
         rooms_count: usize,
-        surgeries_bin: HashSet<Surgery>,
+        resources: Arc<ResourceConfig>,
+        surgeries_bin: SurgeriesBin,
         surgeons_ids: Arc<Vec<SurgeonID>>,
-        max_days_waiting: Arc<HashMap<Priority, DaysWaiting>>,
-        priority_penalties: Arc<HashMap<Priority, u32>>,
+        priority_policy: Arc<PriorityPolicy>,
         intensify_probability: f64,
+        placement_strategy: PlacementStrategy,
+        start_strategy: StartStrategy,
+        horizon: Arc<Horizon>,
+        objective_mode: ObjectiveMode,
         receive_work: Receiver<Option<AntFindSolutionData>>,
-        send_solution: Sender<AntSolution>,
+        send_solution: Sender<Result<AntSolution, SolverError>>,
     ) -> Self {
         Self {
             alpha,
             beta,
-            pheromone_evaporation_rate,
             rooms_count,
+            resources,
             surgeries_bin,
             surgeons_ids: surgeons_ids.clone(),
-            max_days_waiting,
-            priority_penalties,
-            random_number_generator: SmallRng::from_entropy(),
+            priority_policy,
+            // Reseeded with the seed of every solution the ant is asked for
+            random_number_generator: SmallRng::seed_from_u64(0),
             intensify_probability,
+            placement_strategy,
+            start_strategy,
+            horizon,
+            objective_mode,
             receive_work,
             send_solution,
         }
     }
 
+    fn close_week(
+        &self,
+        week: Week,
+        surgeries_bin: &SurgeriesBin,
+        past_weeks: &mut Vec<(Week, f64)>,
+    ) -> Result<Week, SolverError> {
+        // Weeks after every calendar entry are all alike, so the next ones would be empty too
+        let first_day = 7 * week.week_index() as u32 + 1;
+        let last_calendar_day = self
+            .resources
+            .last_calendar_day()
+            .max(self.horizon.last_holiday());
+        if week.is_empty() && first_day > last_calendar_day {
+            if let Some(surgery_id) = surgeries_bin.surgeries().map(|surgery| surgery.id).min() {
+                return Err(SolverError::CapacityExceeded {
+                    surgery_id,
+                    reason: "fits no day of an empty week",
+                });
+            }
+        }
+
+        let objective_function = week
+            .calculate_objective_function(surgeries_bin.surgeries(), self.priority_policy.clone());
+        past_weeks.push((week, objective_function));
+
+        Ok(Week::new(
+            past_weeks.len(),
+            self.rooms_count,
+            self.surgeons_ids.clone(),
+            self.resources.clone(),
+            self.placement_strategy,
+            self.horizon.clone(),
+        ))
+    }
+
     fn choose_first_surgery(
         &mut self,
-        surgeries_bin: &mut HashSet<Surgery>,
+        pheromones: &PheromoneMatrix,
+        surgeries_bin: &mut SurgeriesBin,
         current_week: &mut Option<Week>,
-        current_surgery: &mut Option<Surgery>,
-    ) {
-        let surgeries = surgeries_bin
+        past_weeks: &mut Vec<(Week, f64)>,
+        current_surgery: &mut Option<(usize, Surgery)>,
+    ) -> Result<(), SolverError> {
+        // Sorted by id, like the bin
+        let mut available_surgeries = surgeries_bin
             .iter()
-            .filter(|surgery| surgery.priority == 1)
+            .filter(|(_, surgery)| {
+                current_week
+                    .as_ref()
+                    .is_none_or(|week| week.can_schedule_surgery(surgery))
+            })
             .collect::<Vec<_>>();
 
-        let chosen = surgeries
-            .choose(&mut self.random_number_generator)
-            .unwrap()
-            .clone()
-            .clone();
+        // Surgeons of every surgery may be unavailable on the days this week can still open
+        if available_surgeries.is_empty() {
+            let week = current_week.take().unwrap();
+            *current_week = Some(self.close_week(week, surgeries_bin, past_weeks)?);
+            return Ok(());
+        }
+
+        let (chosen_index, _) = *match self.start_strategy {
+            StartStrategy::HighestPriority => {
+                let priority = available_surgeries
+                    .iter()
+                    .map(|(_, surgery)| surgery.priority)
+                    .min()
+                    .unwrap();
+                available_surgeries.retain(|(_, surgery)| surgery.priority == priority);
+                available_surgeries
+                    .choose(&mut self.random_number_generator)
+                    .unwrap()
+            }
+            StartStrategy::PheromoneWeighted => available_surgeries
+                .choose_weighted(&mut self.random_number_generator, |&(from, _)| {
+                    // No ant goes from a surgery to itself
+                    let pheromone =
+                        pheromones.row(from).iter().sum::<f64>() - pheromones.get(from, from);
+                    pheromone.max(f64::MIN_POSITIVE)
+                })
+                .unwrap(),
+            StartStrategy::MostOverdue => {
+                let overdue_days = |surgery: &Surgery| {
+                    i64::from(surgery.days_waiting)
+                        - i64::from(self.priority_policy.max_days_waiting(surgery.priority))
+                };
+                let most_overdue = available_surgeries
+                    .iter()
+                    .map(|(_, surgery)| overdue_days(surgery))
+                    .max()
+                    .unwrap();
+                available_surgeries.retain(|(_, surgery)| overdue_days(surgery) == most_overdue);
+                available_surgeries
+                    .choose(&mut self.random_number_generator)
+                    .unwrap()
+            }
+            StartStrategy::Uniform => available_surgeries
+                .choose(&mut self.random_number_generator)
+                .unwrap(),
+        };
+        let chosen = surgeries_bin.remove(chosen_index).unwrap();
 
         if let Some(ref mut week) = current_week {
-            week.schedule_surgery(chosen.clone());
+            week.schedule_surgery(chosen.clone())?;
         }
 
-        surgeries_bin.remove(&chosen);
+        *current_surgery = Some((chosen_index, chosen));
 
-        *current_surgery = Some(chosen)
+        Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn choose_next_surgery(
         &mut self,
-        round_number: u32,
-        pheromones: Weak<HashMap<(Surgery, Surgery), f64>>,
-        surgeries_bin: &mut HashSet<Surgery>,
-        path: &mut Vec<(Surgery, Surgery)>,
+        pheromones: Weak<PheromoneMatrix>,
+        surgeries_bin: &mut SurgeriesBin,
+        path: &mut Vec<(usize, usize)>,
         current_week: &mut Option<Week>,
         past_weeks: &mut Vec<(Week, f64)>,
         visited_surgeries: &mut HashSet<Surgery>,
-        current_surgery: &mut Option<Surgery>,
-    ) {
+        current_surgery: &mut Option<(usize, Surgery)>,
+    ) -> Result<(), SolverError> {
         let pheromones = pheromones.upgrade().unwrap();
 
         // First surgery for this ant
         if current_surgery.is_none() {
-            self.choose_first_surgery(surgeries_bin, current_week, current_surgery)
+            self.choose_first_surgery(
+                &pheromones,
+                surgeries_bin,
+                current_week,
+                past_weeks,
+                current_surgery,
+            )?
         } else {
             // All other surgeries
             let mut inner_current_week = current_week.take().unwrap();
-            let week_index = past_weeks.len();
+
+            // Sorted by id, like the bin
+            let available_surgeries = surgeries_bin
+                .iter()
+                .filter(|(_, surgery)| inner_current_week.can_schedule_surgery(surgery))
+                .collect::<Vec<_>>();
+            if available_surgeries.is_empty() {
+                *current_week =
+                    Some(self.close_week(inner_current_week, surgeries_bin, past_weeks)?);
+                return Ok(());
+            }
 
             let current_objective_function = inner_current_week.calculate_objective_function(
-                &surgeries_bin,
-                self.max_days_waiting.clone(),
-                self.priority_penalties.clone(),
-                week_index,
+                surgeries_bin.surgeries(),
+                self.priority_policy.clone(),
             );
 
-            let available_surgeries = inner_current_week.filter_available_surgeries(&surgeries_bin);
-
-            let inner_current_surgery = current_surgery.take().unwrap();
+            let (from, _) = current_surgery.take().unwrap();
 
             let mut surgeries_probability = available_surgeries
-                .iter()
-                .map(|surgery| {
-                    let key = (inner_current_surgery.clone(), surgery.clone());
-                    let pheromone = if pheromones.contains_key(&key) {
-                        pheromones[&key]
-                    } else {
-                        (1.0 - self.pheromone_evaporation_rate).powf((round_number - 1) as f64)
-                    };
-                    let schedule_token = inner_current_week.schedule_surgery(surgery.clone());
+                .into_iter()
+                .map(|(to, surgery)| {
+                    let pheromone = pheromones.get(from, to);
+                    let schedule_token = inner_current_week.schedule_surgery(surgery.clone())?;
                     let objective_function_with_surgery = inner_current_week
                         .calculate_objective_function(
-                            &surgeries_bin,
-                            self.max_days_waiting.clone(),
-                            self.priority_penalties.clone(),
-                            week_index,
+                            surgeries_bin.surgeries(),
+                            self.priority_policy.clone(),
                         );
                     inner_current_week.unschedule_surgery(schedule_token, surgery);
                     let heuristic = current_objective_function - objective_function_with_surgery;
 
-                    (
-                        surgery,
-                        pheromone.powf(self.alpha) * heuristic.powf(self.beta),
-                    )
+                    Ok((to, pheromone.powf(self.alpha) * heuristic.powf(self.beta)))
                 })
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>, SolverError>>()?;
 
             let smallest_value = surgeries_probability
                 .iter()
@@ -162,22 +273,23 @@ impl Ant {
                 value.1 = (value.1 - smallest_value + 0.1) / summation;
             });
 
-            let next_surgery = if self
+            let next_index = if self
                 .random_number_generator
                 .gen_bool(self.intensify_probability)
             {
                 let mut biggest_probability_index = 0;
                 let mut biggest_probability = -f64::INFINITY;
-                surgeries_probability.iter().enumerate().for_each(
-                    |(index, (_surgery, probability))| {
+                surgeries_probability
+                    .iter()
+                    .enumerate()
+                    .for_each(|(index, (_to, probability))| {
                         if *probability > biggest_probability {
                             biggest_probability = *probability;
                             biggest_probability_index = index;
                         }
-                    },
-                );
+                    });
 
-                surgeries_probability[biggest_probability_index].0.clone()
+                surgeries_probability[biggest_probability_index].0
             } else {
                 surgeries_probability
                     .choose_weighted(&mut self.random_number_generator, |surgery_probability| {
@@ -185,79 +297,88 @@ impl Ant {
                     })
                     .unwrap()
                     .0
-                    .clone()
             };
+            let next_surgery = surgeries_bin.remove(next_index).unwrap();
 
-            path.push((inner_current_surgery, next_surgery.clone()));
-            inner_current_week.schedule_surgery(next_surgery.clone());
-            surgeries_bin.remove(&next_surgery);
-            *current_surgery = Some(next_surgery);
+            path.push((from, next_index));
+            inner_current_week.schedule_surgery(next_surgery.clone())?;
+            *current_surgery = Some((next_index, next_surgery));
 
             // If week is full, self.current_week will be a new week
-            if inner_current_week.is_full(&surgeries_bin) {
-                let objective_function = inner_current_week.calculate_objective_function(
-                    &surgeries_bin,
-                    self.max_days_waiting.clone(),
-                    self.priority_penalties.clone(),
-                    week_index,
-                );
-                past_weeks.push((inner_current_week, objective_function));
-                *current_week = Some(Week::new(self.rooms_count, self.surgeons_ids.clone()))
+            if inner_current_week.is_full(surgeries_bin.surgeries()) {
+                *current_week =
+                    Some(self.close_week(inner_current_week, surgeries_bin, past_weeks)?);
             } else {
                 // Otherwise, self.current_week remais the same current_week
                 *current_week = Some(inner_current_week);
             }
         }
 
-        visited_surgeries.insert(current_surgery.clone().unwrap());
+        if let Some((_, surgery)) = current_surgery {
+            visited_surgeries.insert(surgery.clone());
+        }
 
         drop(pheromones);
+
+        Ok(())
     }
 
-    pub fn work(mut self) {
-        while let Some(AntFindSolutionData {
-            pheromones,
-            round_number,
-        }) = self
-            .receive_work
-            .recv()
-            .expect("Failed to receive data to find solution")
-        {
-            let mut current_surgeries_bin = self.surgeries_bin.clone();
-            let mut path = Vec::new();
-            let mut current_week = Some(Week::new(self.rooms_count, self.surgeons_ids.clone()));
-            let mut past_weeks = vec![];
-            let mut visited_surgeries = HashSet::new();
-            let mut current_surgery: Option<Surgery> = None;
-
-            while !current_surgeries_bin.is_empty() {
-                self.choose_next_surgery(
-                    round_number,
-                    pheromones.clone(),
-                    &mut current_surgeries_bin,
-                    &mut path,
-                    &mut current_week,
-                    &mut past_weeks,
-                    &mut visited_surgeries,
-                    &mut current_surgery,
-                );
-            }
+    fn find_solution(
+        &mut self,
+        pheromones: Weak<PheromoneMatrix>,
+    ) -> Result<AntSolution, SolverError> {
+        let mut current_surgeries_bin = self.surgeries_bin.clone();
+        let mut path = Vec::new();
+        let mut current_week = Some(Week::new(
+            0,
+            self.rooms_count,
+            self.surgeons_ids.clone(),
+            self.resources.clone(),
+            self.placement_strategy,
+            self.horizon.clone(),
+        ));
+        let mut past_weeks = vec![];
+        let mut visited_surgeries = HashSet::new();
+        let mut current_surgery: Option<(usize, Surgery)> = None;
+
+        // Surgeries still on the bin after the last week of the horizon stay unscheduled
+        while !current_surgeries_bin.is_empty() && self.horizon.has_week_after(past_weeks.len()) {
+            self.choose_next_surgery(
+                pheromones.clone(),
+                &mut current_surgeries_bin,
+                &mut path,
+                &mut current_week,
+                &mut past_weeks,
+                &mut visited_surgeries,
+                &mut current_surgery,
+            )?;
+        }
+        if self.horizon.has_week_after(past_weeks.len()) {
             let current_week = current_week.take().unwrap();
             let current_week_objective_function = current_week.calculate_objective_function(
-                &current_surgeries_bin,
-                self.max_days_waiting.clone(),
-                self.priority_penalties.clone(),
-                past_weeks.len(),
+                current_surgeries_bin.surgeries(),
+                self.priority_policy.clone(),
             );
             past_weeks.push((current_week, current_week_objective_function));
+        }
 
-            self.send_solution
-                .send(AntSolution {
-                    objective_function_result: past_weeks[0].1,
-                    all_weeks_results: past_weeks,
-                    followed_path: path,
-                })
-                .expect("Failed to send ant solution");
+        Ok(AntSolution {
+            objective_function_result: self.objective_mode.evaluate(&past_weeks),
+            first_week_objective: objective::first_week_objective(&past_weeks),
+            total_objective: objective::total_objective(&past_weeks),
+            all_weeks_results: past_weeks,
+            followed_path: path,
+        })
+    }
+
+    pub fn work(mut self) {
+        // A closed channel means the colony is gone, so there is nothing left to do
+        while let Ok(Some(AntFindSolutionData { pheromones, seed })) = self.receive_work.recv() {
+            self.random_number_generator = SmallRng::seed_from_u64(seed);
+            let solution = self.find_solution(pheromones);
+            if self.send_solution.send(solution).is_err() {
+                break;
+            }
         }
     }
 }
@@ -1,136 +1,200 @@
 mod ant;
+mod pheromone_matrix;
+pub mod start;
+mod surgeries_bin;
+
+pub use ant::AntSolution;
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use std::collections::{HashMap, HashSet};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use super::surgery::{DaysWaiting, Priority, Surgery};
-use crate::solver::surgeon::SurgeonID;
-use crate::solver::week::Week;
-use ant::{Ant, AntFindSolutionData, AntSolution};
+use crate::solver::config::SolverConfig;
+use crate::solver::error::SolverError;
+use crate::solver::instance::Instance;
+use crate::solver::pheromones::PheromoneMap;
+use crate::solver::validation;
+use ant::{Ant, AntFindSolutionData};
+use pheromone_matrix::PheromoneMatrix;
 use std::sync::Arc;
 use std::thread::JoinHandle;
+use surgeries_bin::SurgeriesBin;
 
 struct AntManager {
     ant_thread: JoinHandle<()>,
     send_to_ant: Sender<Option<AntFindSolutionData>>,
-    receive_ant_response: Receiver<AntSolution>,
+    receive_ant_response: Receiver<Result<AntSolution, SolverError>>,
 }
 
 pub struct AntColony {
     ants_threads: Vec<AntManager>,
     ants_count: usize,
-    pheromones: HashMap<(Surgery, Surgery), f64>,
+    pheromones: PheromoneMatrix,
     pheromone_deposit_rate: f64,
     pheromone_evaporation_rate: f64,
     elitism_factor: f64,
+    // Seed the execution was started with, kept across checkpoints
+    master_seed: u64,
+    // Seed of the next round, the seeds of its ants are drawn from it
+    seed: u64,
 }
 
 impl AntColony {
-    pub fn new(
-        treads_count: usize,
-        ants_count: usize,
-        rooms_count: usize,
-        alpha: f64,
-        beta: f64,
-        elitism_factor: f64,
-        pheromone_deposit_rate: f64,
-        pheromone_evaporation_rate: f64,
-        surgeons_ids: Vec<SurgeonID>,
-        surgeries_bin: HashSet<Surgery>,
-        max_days_waiting: HashMap<Priority, DaysWaiting>,
-        priority_penalties: HashMap<Priority, u32>,
-        intensify_probability: f64,
-    ) -> Self {
-        if surgeries_bin.is_empty() {
-            panic!("Unable to solve for a empty set of surgeries!");
-        }
+    pub fn new(config: &SolverConfig, instance: &Instance) -> Result<Self, SolverError> {
+        config.horizon.check()?;
+        Self::check_instance(config, instance)?;
 
-        let max_days_waiting = Arc::new(max_days_waiting);
-        let priority_penalties = Arc::new(priority_penalties);
-        let surgeons_ids = Arc::new(surgeons_ids);
+        let SolverConfig {
+            alpha,
+            beta,
+            pheromone_evaporation_rate,
+            rooms_count,
+            intensify_probability,
+            placement_strategy,
+            start_strategy,
+            objective_mode,
+            ..
+        } = *config;
+        let priority_policy = Arc::new(config.priority_policy.clone());
+        let resources = Arc::new(config.resources.clone());
+        let horizon = Arc::new(config.horizon.clone());
+        let surgeons_ids = Arc::new(instance.surgeons_ids().to_vec());
 
-        let ants = (0..treads_count)
+        let ants = (0..config.threads_count)
             .map(|_| {
                 let (send_to_ant, receive_in_ant) = unbounded();
                 let (send_ant_response, receive_ant_response) = unbounded();
-                let surgeries_bin = surgeries_bin.clone();
+                let surgeries_bin = SurgeriesBin::new(instance.surgeries());
                 let surgeons_ids = surgeons_ids.clone();
-                let max_days_waiting = max_days_waiting.clone();
-                let priority_penalties = priority_penalties.clone();
+                let priority_policy = priority_policy.clone();
+                let resources = resources.clone();
+                let horizon = horizon.clone();
 
-                AntManager {
-                    ant_thread: thread::spawn(move || {
+                Ok(AntManager {
+                    ant_thread: thread::Builder::new().spawn(move || {
                         Ant::new(
                             alpha,
                             beta,
-                            pheromone_evaporation_rate,
                             rooms_count,
+                            resources,
                             surgeries_bin,
                             surgeons_ids,
-                            max_days_waiting,
-                            priority_penalties,
+                            priority_policy,
                             intensify_probability,
+                            placement_strategy,
+                            start_strategy,
+                            horizon,
+                            objective_mode,
                             receive_in_ant,
                             send_ant_response,
                         )
                         .work()
-                    }),
+                    })?,
                     send_to_ant,
                     receive_ant_response,
-                }
+                })
             })
-            .collect::<Vec<AntManager>>();
+            .collect::<Result<Vec<AntManager>, SolverError>>()?;
+
+        let (edges, untouched_pheromone, master_seed, seed) = match &config.resume {
+            Some(checkpoint) => (
+                checkpoint
+                    .pheromones
+                    .iter()
+                    .map(|&(from, to, pheromone)| ((from, to), pheromone))
+                    .collect(),
+                checkpoint.untouched_pheromone,
+                checkpoint.master_seed,
+                checkpoint.seed,
+            ),
+            None => {
+                let seed = config.seed.unwrap_or_else(rand::random);
+                (
+                    config
+                        .initial_pheromones
+                        .clone()
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                    1.0,
+                    seed,
+                    seed,
+                )
+            }
+        };
+        let mut pheromones = PheromoneMatrix::new(instance.surgeries(), untouched_pheromone);
+        // Surgeries cancelled since the pheromones were learned leave their edges behind
+        for ((from, to), pheromone) in edges {
+            if let (Some(from), Some(to)) = (pheromones.index_of(from), pheromones.index_of(to)) {
+                pheromones.set(from, to, pheromone);
+            }
+        }
 
-        Self {
+        Ok(Self {
             ants_threads: ants,
-            ants_count,
-            pheromones: HashMap::new(),
-            pheromone_deposit_rate,
+            ants_count: config.ants_count,
+            pheromones,
+            pheromone_deposit_rate: config.pheromone_deposit_rate,
             pheromone_evaporation_rate,
-            elitism_factor,
+            elitism_factor: config.elitism_factor,
+            master_seed,
+            seed,
+        })
+    }
+
+    fn check_instance(config: &SolverConfig, instance: &Instance) -> Result<(), SolverError> {
+        if instance.surgeries().is_empty() {
+            return Err(SolverError::EmptyInstance);
         }
+
+        for surgery in instance.surgeries() {
+            // A surgery that doesn't fit an empty day would make ants open new weeks forever
+            if let Some(problem) = validation::check_surgery(surgery, config)
+                .into_iter()
+                .next()
+            {
+                return Err(problem.into());
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn round(&mut self, round_number: u32) -> (f64, Vec<(Week, f64)>, Duration) {
+    /// Runs every ant once and returns the best solution of the round.
+    pub fn round(&mut self) -> Result<(AntSolution, Duration), SolverError> {
         let now = Instant::now();
 
-        let mut pheromones = HashMap::new();
-        std::mem::swap(&mut pheromones, &mut self.pheromones);
+        let pheromones = Arc::new(std::mem::take(&mut self.pheromones));
+        let mut random_number_generator = SmallRng::seed_from_u64(self.seed);
 
-        let pheromones = Arc::new(pheromones);
+        for ant_manager in self.ants_threads.iter().cycle().take(self.ants_count) {
+            ant_manager
+                .send_to_ant
+                .send(Some(AntFindSolutionData {
+                    pheromones: Arc::downgrade(&pheromones),
+                    seed: random_number_generator.gen(),
+                }))
+                .map_err(|_| SolverError::ThreadFailure("Failed to sent data to ant".into()))?;
+        }
 
-        self.ants_threads
-            .iter()
-            .cycle()
-            .take(self.ants_count)
-            .for_each(|ant_manager| {
-                ant_manager
-                    .send_to_ant
-                    .send(Some(AntFindSolutionData {
-                        pheromones: Arc::downgrade(&pheromones),
-                        round_number,
-                    }))
-                    .expect("Failed to sent data to ant");
-            });
-
-        let responses = self
+        let mut responses = self
             .ants_threads
             .iter()
             .cycle()
             .take(self.ants_count)
             .map(|ant_manager| {
-                ant_manager
-                    .receive_ant_response
-                    .recv()
-                    .expect("Failed to receive ant response")
+                ant_manager.receive_ant_response.recv().map_err(|_| {
+                    SolverError::ThreadFailure("Failed to receive ant response".into())
+                })?
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, SolverError>>()?;
 
-        self.pheromones = Arc::try_unwrap(pheromones).unwrap();
+        self.pheromones = Arc::try_unwrap(pheromones).map_err(|_| {
+            SolverError::ThreadFailure("An ant is still holding the pheromones".into())
+        })?;
 
-        let mut pheromones_by_path = HashMap::<(Surgery, Surgery), f64>::new();
         let mut best_objective_function = f64::INFINITY;
         let mut best_index = 0;
 
@@ -141,58 +205,156 @@ impl AntColony {
             }
         });
 
-        let best_scheduling = responses[best_index].all_weeks_results.clone();
-
         let best_paths_set = responses[best_index]
             .followed_path
             .iter()
             .cloned()
-            .collect::<HashSet<(Surgery, Surgery)>>();
+            .collect::<HashSet<(usize, usize)>>();
 
         let elitism_factor = self.elitism_factor;
 
-        responses.into_iter().for_each(|result| {
-            for path in result.followed_path {
+        // Every edge evaporates, then the ones used this round get their deposits
+        self.pheromones.evaporate(self.pheromone_evaporation_rate);
+        for result in &responses {
+            for &(from, to) in &result.followed_path {
                 let delta = self.pheromone_deposit_rate / result.objective_function_result;
-                let elitism_delta = if best_paths_set.contains(&path) {
+                let elitism_delta = if best_paths_set.contains(&(from, to)) {
                     elitism_factor * delta
                 } else {
                     0.0
                 };
-                *pheromones_by_path.entry(path).or_default() += delta + elitism_delta;
+                self.pheromones.add(from, to, delta + elitism_delta);
             }
-        });
+        }
 
-        let pheromone_evaporation_rate = self.pheromone_evaporation_rate;
+        self.seed = random_number_generator.gen();
 
-        let mut updated_paths = HashSet::new();
-        for (path, new_pheromone) in pheromones_by_path {
-            let total_pheromone = self.pheromones.entry(path.clone()).or_insert_with(|| {
-                (1.0 - pheromone_evaporation_rate).powf((round_number - 1) as f64)
-            });
+        Ok((responses.swap_remove(best_index), now.elapsed()))
+    }
 
-            *total_pheromone =
-                *total_pheromone * (1.0 - pheromone_evaporation_rate) + new_pheromone;
-            updated_paths.insert(path);
-        }
+    /// Current pheromones keyed by surgery ids, relative to an edge no ant has used yet.
+    pub fn pheromones(&self) -> PheromoneMap {
+        let unused_edge_pheromone = self.pheromones.untouched().max(f64::MIN_POSITIVE);
 
         self.pheromones
-            .iter_mut()
-            .filter(|(key, _value)| !updated_paths.contains(key))
-            .for_each(|(_key, value)| {
-                *value *= 1.0 - pheromone_evaporation_rate;
-            });
+            .touched_edges()
+            .map(|(from, to, pheromone)| ((from, to), pheromone / unused_edge_pheromone))
+            .collect()
+    }
+
+    /// Pheromones as stored by the colony, sorted by surgery ids, to be saved on a checkpoint.
+    pub fn raw_pheromones(&self) -> Vec<(usize, usize, f64)> {
+        self.pheromones.touched_edges().collect()
+    }
 
-        (best_objective_function, best_scheduling, now.elapsed())
+    /// Pheromone of the edges no ant has used yet, left out of `raw_pheromones`.
+    pub fn untouched_pheromone(&self) -> f64 {
+        self.pheromones.untouched()
     }
 
-    pub fn kill_ants(&mut self) {
+    pub fn master_seed(&self) -> u64 {
+        self.master_seed
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn kill_ants(&mut self) -> Result<(), SolverError> {
         let mut ants_to_kill = Vec::new();
         std::mem::swap(&mut ants_to_kill, &mut self.ants_threads);
 
-        ants_to_kill.into_iter().for_each(|ant_manager| {
-            ant_manager.send_to_ant.send(None).unwrap();
-            ant_manager.ant_thread.join().unwrap();
-        })
+        // Every ant must be told to stop before we report any failure, otherwise its thread leaks
+        let mut result = Ok(());
+        for ant_manager in ants_to_kill {
+            // A failed send means the ant already stopped, so joining is enough
+            let _ = ant_manager.send_to_ant.send(None);
+            if ant_manager.ant_thread.join().is_err() && result.is_ok() {
+                result = Err(SolverError::ThreadFailure("An ant thread panicked".into()));
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::checkpoint::Checkpoint;
+    use crate::solver::surgery::Surgery;
+    use crate::solver::Solver;
+    use start::StartStrategy;
+
+    #[test]
+    fn pheromones_are_relative_to_an_untouched_edge() {
+        let instance =
+            Instance::from_surgeries((1..=3).map(|id| Surgery::new(id, 10, 0, 1, 1, 1)).collect());
+        // Three rounds at 0.5 leave 0.125 on the edges no ant used
+        let checkpoint = Checkpoint {
+            round: 3,
+            master_seed: 1,
+            seed: 1,
+            elapsed_time: Duration::default(),
+            best_round: 1,
+            best_objective: f64::INFINITY,
+            best_first_week_objective: f64::INFINITY,
+            best_total_objective: f64::INFINITY,
+            best_assignments: Vec::new(),
+            pheromones: vec![(1, 2, 0.5), (2, 1, 0.125), (9, 1, 2.0)],
+            untouched_pheromone: 0.125,
+        };
+        let config = SolverConfig::new()
+            .threads_count(1)
+            .ants_count(2)
+            .pheromone_evaporation_rate(0.5)
+            .resume_from(checkpoint);
+        let mut colony = AntColony::new(&config, &instance).unwrap();
+
+        assert_eq!(colony.raw_pheromones(), vec![(1, 2, 0.5)]);
+        assert_eq!(colony.pheromones(), PheromoneMap::from([((1, 2), 4.0)]));
+
+        colony.round().unwrap();
+        let pheromones = colony.pheromones();
+        let raw_pheromones = colony.raw_pheromones();
+        assert_eq!(pheromones.len(), raw_pheromones.len());
+        for (from, to, pheromone) in raw_pheromones {
+            assert_eq!(pheromones[&(from, to)], pheromone / 0.0625);
+        }
+        colony.kill_ants().unwrap();
+    }
+
+    #[test]
+    fn every_start_strategy_fills_the_first_week_without_priority_1() {
+        let instance = Instance::from_surgeries(
+            (1..=12)
+                .map(|id| Surgery::new(id, 10, 5 * id as u32, id % 3 + 2, 1, id % 4 + 1))
+                .collect(),
+        );
+        for start_strategy in [
+            StartStrategy::HighestPriority,
+            StartStrategy::PheromoneWeighted,
+            StartStrategy::MostOverdue,
+            StartStrategy::Uniform,
+        ] {
+            let config = SolverConfig::new()
+                .threads_count(1)
+                .ants_count(2)
+                .max_rounds_count(3)
+                .start_strategy(start_strategy)
+                .seed(3);
+            let solution = Solver::solve(&instance, &config).unwrap();
+
+            let (first_week, _) = &solution.weeks[0];
+            assert!(!first_week.is_empty(), "{}", start_strategy);
+            assert!(
+                solution
+                    .assignments
+                    .iter()
+                    .any(|assignment| assignment.day <= 7),
+                "{}",
+                start_strategy
+            );
+        }
     }
 }
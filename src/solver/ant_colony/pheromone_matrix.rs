@@ -0,0 +1,180 @@
+use crate::solver::surgery::Surgery;
+use std::collections::HashMap;
+
+/// Pheromones of every edge between surgeries, on a contiguous row major matrix indexed by the
+/// position of each surgery when sorted by id.
+#[derive(Debug, Clone, Default)]
+pub struct PheromoneMatrix {
+    ids: Vec<usize>,
+    indices: HashMap<usize, usize>,
+    values: Vec<f64>,
+    // Pheromone of the edges no ant has used yet, evaporated the same way as the matrix, so those
+    // edges are still equal to it
+    untouched: f64,
+}
+
+impl PheromoneMatrix {
+    /// Every edge between the surgeries starts with `initial` pheromones.
+    pub fn new<'a, I: IntoIterator<Item = &'a Surgery>>(surgeries: I, initial: f64) -> Self {
+        let mut ids = surgeries
+            .into_iter()
+            .map(|surgery| surgery.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        let indices = ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, index))
+            .collect();
+
+        Self {
+            values: vec![initial; ids.len() * ids.len()],
+            ids,
+            indices,
+            untouched: initial,
+        }
+    }
+
+    pub fn index_of(&self, surgery_id: usize) -> Option<usize> {
+        self.indices.get(&surgery_id).copied()
+    }
+
+    pub fn get(&self, from: usize, to: usize) -> f64 {
+        self.values[from * self.ids.len() + to]
+    }
+
+    pub fn set(&mut self, from: usize, to: usize, pheromone: f64) {
+        let surgeries_count = self.ids.len();
+        self.values[from * surgeries_count + to] = pheromone;
+    }
+
+    pub fn add(&mut self, from: usize, to: usize, pheromone: f64) {
+        let surgeries_count = self.ids.len();
+        self.values[from * surgeries_count + to] += pheromone;
+    }
+
+    /// Pheromones of the edges leaving the surgery, including the one back to itself.
+    pub fn row(&self, from: usize) -> &[f64] {
+        let surgeries_count = self.ids.len();
+        &self.values[from * surgeries_count..(from + 1) * surgeries_count]
+    }
+
+    pub fn evaporate(&mut self, evaporation_rate: f64) {
+        let factor = 1.0 - evaporation_rate;
+        self.values.iter_mut().for_each(|value| *value *= factor);
+        self.untouched *= factor;
+    }
+
+    pub fn untouched(&self) -> f64 {
+        self.untouched
+    }
+
+    /// Edges some ant has used, or that were seeded, as `(from, to, pheromone)` surgery ids
+    /// sorted by ids.
+    pub fn touched_edges(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        let surgeries_count = self.ids.len();
+        self.values
+            .iter()
+            .enumerate()
+            .filter(move |&(_, &value)| value != self.untouched)
+            .map(move |(index, &value)| {
+                (
+                    self.ids[index / surgeries_count],
+                    self.ids[index % surgeries_count],
+                    value,
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVAPORATION_RATE: f64 = 0.2;
+
+    fn matrix(ids: &[usize]) -> PheromoneMatrix {
+        let surgeries = ids
+            .iter()
+            .map(|&id| Surgery::new(id, 10, 0, 1, 1, 1))
+            .collect::<Vec<_>>();
+        PheromoneMatrix::new(&surgeries, 1.0)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs(),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn evaporating_everything_matches_the_map_of_used_edges() {
+        let ids = [3, 10, 7];
+        let mut matrix = matrix(&ids);
+        // Pheromones the colony kept before the matrix, only for edges some ant had used
+        let mut map = HashMap::<(usize, usize), f64>::new();
+        let deposits = [
+            vec![((3, 10), 5.0), ((10, 7), 2.0)],
+            vec![],
+            vec![((3, 10), 1.0), ((7, 3), 4.0)],
+            vec![((10, 7), 0.5)],
+        ];
+
+        for (round_index, round_deposits) in deposits.iter().enumerate() {
+            let round_number = round_index as i32 + 1;
+            for &((from, to), deposit) in round_deposits {
+                let pheromone = map
+                    .entry((from, to))
+                    .or_insert_with(|| (1.0 - EVAPORATION_RATE).powi(round_number - 1));
+                *pheromone = *pheromone * (1.0 - EVAPORATION_RATE) + deposit;
+            }
+            for (edge, pheromone) in map.iter_mut() {
+                if !round_deposits
+                    .iter()
+                    .any(|(deposited, _)| deposited == edge)
+                {
+                    *pheromone *= 1.0 - EVAPORATION_RATE;
+                }
+            }
+
+            matrix.evaporate(EVAPORATION_RATE);
+            for &((from, to), deposit) in round_deposits {
+                let (from, to) = (matrix.index_of(from).unwrap(), matrix.index_of(to).unwrap());
+                matrix.add(from, to, deposit);
+            }
+
+            let unseen = (1.0 - EVAPORATION_RATE).powi(round_number);
+            assert_close(matrix.untouched(), unseen);
+            for &from in &ids {
+                for &to in &ids {
+                    let expected = map.get(&(from, to)).copied().unwrap_or(unseen);
+                    let (from, to) = (matrix.index_of(from).unwrap(), matrix.index_of(to).unwrap());
+                    assert_close(matrix.get(from, to), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn touched_edges_skip_the_untouched_ones_and_are_sorted_by_id() {
+        let mut matrix = matrix(&[7, 3, 10]);
+        assert_eq!(matrix.index_of(3), Some(0));
+        assert_eq!(matrix.index_of(10), Some(2));
+        assert_eq!(matrix.index_of(4), None);
+
+        matrix.set(2, 0, 3.0);
+        matrix.add(0, 1, 0.5);
+        matrix.evaporate(0.5);
+        // Set back to the value of an untouched edge, so it can't be told apart from one
+        matrix.set(1, 1, 0.5);
+
+        assert_eq!(
+            matrix.touched_edges().collect::<Vec<_>>(),
+            vec![(3, 7, 0.75), (10, 3, 1.5)]
+        );
+        assert_eq!(matrix.row(2), &[1.5, 0.5, 0.5]);
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How each ant picks the first surgery of its tour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartStrategy {
+    /// At random among the surgeries of the most urgent priority still available.
+    #[default]
+    HighestPriority,
+    /// At random, weighted by the pheromones on the edges leaving each surgery.
+    PheromoneWeighted,
+    /// The surgery furthest past the deadline of its priority, at random on ties.
+    MostOverdue,
+    /// At random among every available surgery.
+    Uniform,
+}
+
+impl FromStr for StartStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "highest_priority" => Ok(StartStrategy::HighestPriority),
+            "pheromone" => Ok(StartStrategy::PheromoneWeighted),
+            "most_overdue" => Ok(StartStrategy::MostOverdue),
+            "uniform" => Ok(StartStrategy::Uniform),
+            _ => Err(format!(
+                "unknown start strategy {}, expected highest_priority, pheromone, most_overdue or uniform",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StartStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartStrategy::HighestPriority => write!(f, "highest_priority"),
+            StartStrategy::PheromoneWeighted => write!(f, "pheromone"),
+            StartStrategy::MostOverdue => write!(f, "most_overdue"),
+            StartStrategy::Uniform => write!(f, "uniform"),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::solver::surgery::Surgery;
+
+/// Surgeries an ant still has to schedule, each one at its position on the `PheromoneMatrix`, so
+/// the pheromones of a candidate are read without hashing it.
+#[derive(Debug, Clone, Default)]
+pub struct SurgeriesBin {
+    // Sorted by id like the matrix, `None` once the surgery leaves the bin
+    surgeries: Vec<Option<Surgery>>,
+    len: usize,
+}
+
+impl SurgeriesBin {
+    pub fn new<'a, I: IntoIterator<Item = &'a Surgery>>(surgeries: I) -> Self {
+        let mut surgeries = surgeries.into_iter().cloned().collect::<Vec<_>>();
+        surgeries.sort_unstable_by_key(|surgery| surgery.id);
+
+        Self {
+            len: surgeries.len(),
+            surgeries: surgeries.into_iter().map(Some).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Surgeries left with their index on the pheromone matrix, sorted by id.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Surgery)> + '_ {
+        self.surgeries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, surgery)| surgery.as_ref().map(|surgery| (index, surgery)))
+    }
+
+    /// Surgeries left, sorted by id.
+    pub fn surgeries(&self) -> impl Iterator<Item = &Surgery> + '_ {
+        self.surgeries.iter().flatten()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Surgery> {
+        let surgery = self.surgeries[index].take();
+        if surgery.is_some() {
+            self.len -= 1;
+        }
+
+        surgery
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::ant_colony::pheromone_matrix::PheromoneMatrix;
+
+    #[test]
+    fn surgeries_keep_their_pheromone_matrix_index() {
+        let surgeries = [7, 2, 9, 4]
+            .iter()
+            .map(|&id| Surgery::new(id, 10, 0, 1, 1, 1))
+            .collect::<Vec<_>>();
+        let pheromones = PheromoneMatrix::new(&surgeries, 1.0);
+        let mut bin = SurgeriesBin::new(&surgeries);
+
+        assert_eq!(bin.remove(pheromones.index_of(4).unwrap()).unwrap().id, 4);
+        assert_eq!(bin.remove(pheromones.index_of(4).unwrap()), None);
+        let left = bin
+            .iter()
+            .map(|(index, surgery)| (index, surgery.id))
+            .collect::<Vec<_>>();
+        let expected = [2, 7, 9]
+            .iter()
+            .map(|&id| (pheromones.index_of(id).unwrap(), id))
+            .collect::<Vec<_>>();
+        assert_eq!(left, expected);
+        assert!(!bin.is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use crate::solver::surgeon::SurgeonID;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+/// What a surgeon can do on a single day, like a clinic day, a vacation or an on-call shift.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayAvailability {
+    pub surgeon: SurgeonID,
+    /// Day numbered from 1, the same way days are numbered on the objective function.
+    pub day: u32,
+    /// Slot windows, as `[start, end]` pairs, where the surgeon may operate. An empty list means
+    /// the surgeon is off, while leaving it out allows the whole day.
+    pub windows: Option<Vec<(u8, u8)>>,
+    pub max_day_time: Option<u8>,
+}
+
+impl DayAvailability {
+    pub fn windows(&self) -> Option<Vec<Range<u8>>> {
+        self.windows.as_ref().map(|windows| {
+            windows
+                .iter()
+                .map(|&(start, end)| start..end)
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AvailabilityFile {
+    availability: Vec<DayAvailability>,
+}
+
+/// Reads an `availability` list of surgeon days.
+pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Vec<DayAvailability>, SolverError> {
+    Ok(config::load::<AvailabilityFile, _>(filename)?.availability)
+}
@@ -0,0 +1,32 @@
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use crate::solver::rooms::RoomID;
+use crate::solver::surgery::Speciality;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A room reserved for a speciality on one day of every week, from the master surgical schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    /// Day of the week, numbered from 1.
+    pub weekday: u32,
+    pub room: RoomID,
+    pub speciality: Speciality,
+}
+
+impl Block {
+    /// Whether the block repeats on the given day, numbered from 1 for the whole schedule.
+    pub fn is_on_day(&self, day: u32) -> bool {
+        (day - 1) % 7 + 1 == self.weekday
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlocksFile {
+    block: Vec<Block>,
+}
+
+/// Reads the master schedule from a `block` list.
+pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Vec<Block>, SolverError> {
+    Ok(config::load::<BlocksFile, _>(filename)?.block)
+}
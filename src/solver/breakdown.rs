@@ -0,0 +1,305 @@
+use crate::solver::config::SolverConfig;
+use crate::solver::error::SolverError;
+use crate::solver::instance::Instance;
+use crate::solver::solution::Assignment;
+use crate::solver::surgery::{Priority, Surgery};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Terms summed by `Week::calculate_objective_function`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CostComponent {
+    /// Squared days waited by a scheduled surgery.
+    Waiting,
+    /// Squared days a scheduled surgery waited past the deadline of its priority.
+    Overdue,
+    /// Penalty of priorities that should be performed on the first day of the week.
+    FirstDay,
+    /// Cost of a surgery still waiting at the end of the week.
+    NotScheduled,
+}
+
+impl CostComponent {
+    pub const ALL: [CostComponent; 4] = [
+        CostComponent::Waiting,
+        CostComponent::Overdue,
+        CostComponent::FirstDay,
+        CostComponent::NotScheduled,
+    ];
+}
+
+impl fmt::Display for CostComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CostComponent::Waiting => "waiting",
+            CostComponent::Overdue => "overdue",
+            CostComponent::FirstDay => "first_day",
+            CostComponent::NotScheduled => "not_scheduled",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Cost of one surgery on one week of the schedule, already weighted by the objective mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurgeryCost {
+    pub surgery_id: usize,
+    pub priority: Priority,
+    /// Week numbered from 1.
+    pub week: usize,
+    /// Day the surgery was performed on, `None` while it is still waiting.
+    pub day: Option<u32>,
+    pub waiting: f64,
+    pub overdue: f64,
+    pub first_day: f64,
+    pub not_scheduled: f64,
+}
+
+impl SurgeryCost {
+    pub fn component(&self, component: CostComponent) -> f64 {
+        match component {
+            CostComponent::Waiting => self.waiting,
+            CostComponent::Overdue => self.overdue,
+            CostComponent::FirstDay => self.first_day,
+            CostComponent::NotScheduled => self.not_scheduled,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.waiting + self.overdue + self.first_day + self.not_scheduled
+    }
+}
+
+/// Objective of a schedule split per surgery, week and component, summing up to the value the
+/// colony optimises.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectiveBreakdown {
+    pub costs: Vec<SurgeryCost>,
+}
+
+impl ObjectiveBreakdown {
+    pub fn total(&self) -> f64 {
+        self.costs.iter().map(SurgeryCost::total).sum()
+    }
+
+    pub fn by_component(&self) -> BTreeMap<CostComponent, f64> {
+        CostComponent::ALL
+            .iter()
+            .map(|&component| {
+                let cost = self
+                    .costs
+                    .iter()
+                    .map(|cost| cost.component(component))
+                    .sum();
+                (component, cost)
+            })
+            .collect()
+    }
+
+    pub fn by_priority(&self) -> BTreeMap<Priority, f64> {
+        let mut priorities = BTreeMap::new();
+        for cost in &self.costs {
+            *priorities.entry(cost.priority).or_default() += cost.total();
+        }
+
+        priorities
+    }
+
+    /// Total cost of each surgery over every week, the most expensive first.
+    pub fn by_surgery(&self) -> Vec<(usize, f64)> {
+        let mut surgeries = BTreeMap::new();
+        for cost in &self.costs {
+            *surgeries.entry(cost.surgery_id).or_insert(0.0) += cost.total();
+        }
+        let mut surgeries = surgeries.into_iter().collect::<Vec<_>>();
+        surgeries.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        surgeries
+    }
+
+    /// Writes a line per surgery and week, with the weighted value of each component.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "Cirurgia (c);Prioridade (p);Semana;Dia (d);{};total",
+            CostComponent::ALL
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(";")
+        )?;
+        for cost in &self.costs {
+            write!(
+                writer,
+                "{};{};{};",
+                cost.surgery_id, cost.priority, cost.week
+            )?;
+            if let Some(day) = cost.day {
+                write!(writer, "{}", day)?;
+            }
+            for component in CostComponent::ALL {
+                write!(writer, ";{}", cost.component(component))?;
+            }
+            writeln!(writer, ";{}", cost.total())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ObjectiveBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Objective breakdown: {}", self.total())?;
+        for (component, cost) in self.by_component() {
+            write!(f, "\n  {}: {}", component, cost)?;
+        }
+        for (priority, cost) in self.by_priority() {
+            write!(f, "\n  priority {}: {}", priority, cost)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the objective of a schedule the same way the ants compute it: every week adds the
+/// costs of its scheduled surgeries plus the cost of every surgery still waiting at its end,
+/// weighted by `ObjectiveMode::week_weight`.
+///
+/// Works on assignments so it also covers schedules read back from a csv. The schedule spans the
+/// weeks up to its last assignment, or the whole horizon when surgeries are left waiting on it.
+/// Assignments on day 0, which `evaluation::evaluate` reports, leave their surgery waiting.
+pub fn breakdown(
+    instance: &Instance,
+    assignments: &[Assignment],
+    config: &SolverConfig,
+) -> Result<ObjectiveBreakdown, SolverError> {
+    let priority_policy = &config.priority_policy;
+    // Costs of a priority come from its level on the policy
+    if let Some(surgery) = instance
+        .surgeries()
+        .iter()
+        .filter(|surgery| !priority_policy.contains(surgery.priority))
+        .min_by_key(|surgery| surgery.id)
+    {
+        return Err(SolverError::UnknownPriority {
+            surgery_id: surgery.id,
+            priority: surgery.priority,
+        });
+    }
+
+    let surgeries_by_id = instance
+        .surgeries()
+        .iter()
+        .map(|surgery| (surgery.id, surgery))
+        .collect::<HashMap<_, _>>();
+
+    // Surgeries of the instance on the first day they were assigned to
+    let mut scheduled_days = HashMap::<usize, u32>::new();
+    for assignment in assignments {
+        if surgeries_by_id.contains_key(&assignment.surgery_id) && assignment.day > 0 {
+            scheduled_days
+                .entry(assignment.surgery_id)
+                .or_insert(assignment.day);
+        }
+    }
+    let week_of = |day: u32| (day as usize - 1) / 7;
+
+    let last_week = scheduled_days.values().map(|&day| week_of(day)).max();
+    let weeks_count = match config.horizon.weeks {
+        Some(weeks) if scheduled_days.len() < surgeries_by_id.len() => {
+            weeks.max(last_week.map_or(0, |week| week + 1))
+        }
+        _ => last_week.map_or(1, |week| week + 1),
+    };
+
+    let mut surgeries = surgeries_by_id.values().cloned().collect::<Vec<&Surgery>>();
+    surgeries.sort_by_key(|surgery| surgery.id);
+
+    let mut costs = Vec::new();
+    for week_index in 0..weeks_count {
+        let weight = config.objective_mode.week_weight(week_index);
+        if weight == 0.0 {
+            continue;
+        }
+
+        for surgery in &surgeries {
+            let cost = match scheduled_days.get(&surgery.id) {
+                Some(&day) if week_of(day) == week_index => SurgeryCost {
+                    surgery_id: surgery.id,
+                    priority: surgery.priority,
+                    week: week_index + 1,
+                    day: Some(day),
+                    waiting: weight * surgery.waiting_cost(day),
+                    overdue: weight * surgery.overdue_cost(priority_policy, day),
+                    first_day: if day != 1 {
+                        weight
+                            * surgery.penalty_for_not_scheduling_on_first_day(priority_policy, day)
+                    } else {
+                        0.0
+                    },
+                    not_scheduled: 0.0,
+                },
+                // Performed on a previous week
+                Some(&day) if week_of(day) < week_index => continue,
+                _ => SurgeryCost {
+                    surgery_id: surgery.id,
+                    priority: surgery.priority,
+                    week: week_index + 1,
+                    day: None,
+                    waiting: 0.0,
+                    overdue: 0.0,
+                    first_day: 0.0,
+                    not_scheduled: weight
+                        * surgery.not_scheduled_objective_function(priority_policy),
+                },
+            };
+            costs.push(cost);
+        }
+    }
+
+    Ok(ObjectiveBreakdown { costs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assignment(surgery_id: usize, day: u32) -> Assignment {
+        Assignment {
+            surgery_id,
+            room: 1,
+            day,
+            date: None,
+            start: 1,
+        }
+    }
+
+    #[test]
+    fn unknown_priorities_are_errors() {
+        let instance = Instance::from_surgeries(vec![
+            Surgery::new(1, 10, 0, 1, 1, 1),
+            Surgery::new(2, 10, 0, 9, 1, 1),
+        ]);
+
+        assert!(matches!(
+            breakdown(&instance, &[assignment(1, 1)], &SolverConfig::new()),
+            Err(SolverError::UnknownPriority {
+                surgery_id: 2,
+                priority: 9
+            })
+        ));
+    }
+
+    #[test]
+    fn surgeries_on_day_zero_are_still_waiting() {
+        let instance = Instance::from_surgeries(vec![Surgery::new(1, 10, 0, 1, 1, 1)]);
+        let config = SolverConfig::new();
+
+        let breakdown = breakdown(&instance, &[assignment(1, 0)], &config).unwrap();
+        assert_eq!(breakdown.costs.len(), 1);
+        assert_eq!(breakdown.costs[0].day, None);
+        assert!(breakdown.costs[0].not_scheduled > 0.0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::solver::error::SolverError;
+use crate::solver::solution::Assignment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// State of a solver execution after a round, enough to carry on from the next one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Last round completed.
+    pub round: u32,
+    /// Seed the execution was started with, the one to pass to `--seed` to replay it.
+    pub master_seed: u64,
+    /// Seed the colony draws the ants seeds of the next round from.
+    pub seed: u64,
+    /// Time spent on the rounds already completed.
+    pub elapsed_time: Duration,
+    pub best_round: u32,
+    pub best_objective: f64,
+    pub best_first_week_objective: f64,
+    pub best_total_objective: f64,
+    pub best_assignments: Vec<Assignment>,
+    /// Pheromones of the colony as `(from, to, pheromone)` surgery ids, not normalised like a
+    /// `PheromoneMap`, so the colony picks up exactly where it stopped.
+    pub pheromones: Vec<(usize, usize, f64)>,
+    /// Pheromone of the edges missing from `pheromones`, saved rather than evaporated again from
+    /// the round count, which would round differently.
+    pub untouched_pheromone: f64,
+}
+
+impl Checkpoint {
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Self, SolverError> {
+        let content = fs::read_to_string(filename)?;
+
+        serde_json::from_str(&content).map_err(|error| SolverError::Config(error.to_string()))
+    }
+
+    /// Writes the checkpoint as JSON on a temporary file first, so an interruption while saving
+    /// keeps the previous checkpoint intact.
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> Result<(), SolverError> {
+        let filename = filename.as_ref();
+        let content =
+            serde_json::to_string(self).map_err(|error| SolverError::Config(error.to_string()))?;
+        let temporary_file = filename.with_extension("tmp");
+        fs::write(&temporary_file, content)?;
+        fs::rename(temporary_file, filename)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn saved_checkpoints_are_read_back() {
+        let checkpoint = Checkpoint {
+            round: 7,
+            master_seed: 42,
+            seed: 1234,
+            elapsed_time: Duration::from_millis(1500),
+            best_round: 5,
+            best_objective: 10.5,
+            best_first_week_objective: 10.5,
+            best_total_objective: 20.25,
+            best_assignments: vec![Assignment {
+                surgery_id: 3,
+                room: 1,
+                day: 2,
+                date: None,
+                start: 11,
+            }],
+            pheromones: vec![(1, 3, 0.5), (3, 2, 0.125)],
+            untouched_pheromone: 0.0625,
+        };
+        let directory = env::temp_dir().join(format!("checkpoint_test_{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let filename = directory.join("state.json");
+
+        // The second save replaces the first through the temporary file
+        checkpoint.save(&filename).unwrap();
+        let later = Checkpoint {
+            round: 8,
+            ..checkpoint.clone()
+        };
+        later.save(&filename).unwrap();
+        let read = Checkpoint::from_file(&filename);
+        let leftovers = fs::read_dir(&directory).unwrap().count();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(read.unwrap(), later);
+        assert_eq!(leftovers, 1);
+    }
+}
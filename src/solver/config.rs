@@ -0,0 +1,245 @@
+use crate::solver::ant_colony::start::StartStrategy;
+use crate::solver::checkpoint::Checkpoint;
+use crate::solver::error::SolverError;
+use crate::solver::horizon::Horizon;
+use crate::solver::objective::ObjectiveMode;
+use crate::solver::pheromones::PheromoneMap;
+use crate::solver::policy::PriorityPolicy;
+use crate::solver::resources::ResourceConfig;
+use crate::solver::week::placement::PlacementStrategy;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Every knob of the ACO, built with chained setters starting from the defaults used by the binary.
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    pub(crate) threads_count: usize,
+    pub(crate) ants_count: usize,
+    pub(crate) rooms_count: usize,
+    pub(crate) priority_policy: PriorityPolicy,
+    pub(crate) resources: ResourceConfig,
+    pub(crate) horizon: Horizon,
+    pub(crate) alpha: f64,
+    pub(crate) beta: f64,
+    pub(crate) elitism_factor: f64,
+    pub(crate) pheromone_deposit_rate: f64,
+    pub(crate) pheromone_evaporation_rate: f64,
+    pub(crate) max_rounds_count: u32,
+    pub(crate) max_rounds_without_improvement: u32,
+    pub(crate) target: f64,
+    pub(crate) intensify_probability: f64,
+    pub(crate) placement_strategy: PlacementStrategy,
+    pub(crate) start_strategy: StartStrategy,
+    pub(crate) objective_mode: ObjectiveMode,
+    pub(crate) initial_pheromones: PheromoneMap,
+    pub(crate) pheromones_file: Option<PathBuf>,
+    pub(crate) pheromones_interval: u32,
+    pub(crate) checkpoint_file: Option<PathBuf>,
+    pub(crate) checkpoint_interval: u32,
+    pub(crate) resume: Option<Checkpoint>,
+    pub(crate) stop_flag: Option<Arc<AtomicBool>>,
+    pub(crate) time_limit: Option<Duration>,
+    pub(crate) seed: Option<u64>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            threads_count: 8,
+            ants_count: 8,
+            rooms_count: 1,
+            priority_policy: PriorityPolicy::default(),
+            resources: ResourceConfig::default(),
+            horizon: Horizon::default(),
+            alpha: 1.0,
+            beta: 1.0,
+            elitism_factor: 1.0,
+            pheromone_deposit_rate: 10000.0,
+            pheromone_evaporation_rate: 0.2,
+            max_rounds_count: 1000,
+            max_rounds_without_improvement: 500,
+            target: 0.0,
+            intensify_probability: 0.0,
+            placement_strategy: PlacementStrategy::default(),
+            start_strategy: StartStrategy::default(),
+            objective_mode: ObjectiveMode::default(),
+            initial_pheromones: PheromoneMap::new(),
+            pheromones_file: None,
+            pheromones_interval: 0,
+            checkpoint_file: None,
+            checkpoint_interval: 0,
+            resume: None,
+            stop_flag: None,
+            time_limit: None,
+            seed: None,
+        }
+    }
+}
+
+impl SolverConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn threads_count(mut self, threads_count: usize) -> Self {
+        self.threads_count = threads_count;
+        self
+    }
+
+    pub fn ants_count(mut self, ants_count: usize) -> Self {
+        self.ants_count = ants_count;
+        self
+    }
+
+    pub fn rooms_count(mut self, rooms_count: usize) -> Self {
+        self.rooms_count = rooms_count;
+        self
+    }
+
+    pub fn priority_policy(mut self, priority_policy: PriorityPolicy) -> Self {
+        self.priority_policy = priority_policy;
+        self
+    }
+
+    pub fn resources(mut self, resources: ResourceConfig) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    pub fn horizon(mut self, horizon: Horizon) -> Self {
+        self.horizon = horizon;
+        self
+    }
+
+    pub fn alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Change to 0 to not use elitism on pheromones.
+    pub fn elitism_factor(mut self, elitism_factor: f64) -> Self {
+        self.elitism_factor = elitism_factor;
+        self
+    }
+
+    pub fn pheromone_deposit_rate(mut self, pheromone_deposit_rate: f64) -> Self {
+        self.pheromone_deposit_rate = pheromone_deposit_rate;
+        self
+    }
+
+    pub fn pheromone_evaporation_rate(mut self, pheromone_evaporation_rate: f64) -> Self {
+        self.pheromone_evaporation_rate = pheromone_evaporation_rate;
+        self
+    }
+
+    pub fn max_rounds_count(mut self, max_rounds_count: u32) -> Self {
+        self.max_rounds_count = max_rounds_count;
+        self
+    }
+
+    pub fn max_rounds_without_improvement(mut self, max_rounds_without_improvement: u32) -> Self {
+        self.max_rounds_without_improvement = max_rounds_without_improvement;
+        self
+    }
+
+    /// When different from 0, rounds run until this objective value is reached.
+    pub fn target(mut self, target: f64) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn intensify_probability(mut self, intensify_probability: f64) -> Self {
+        self.intensify_probability = intensify_probability;
+        self
+    }
+
+    /// How surgeries are placed on a room day, appending after the last surgery by default.
+    pub fn placement_strategy(mut self, placement_strategy: PlacementStrategy) -> Self {
+        self.placement_strategy = placement_strategy;
+        self
+    }
+
+    /// How each ant picks the first surgery of its tour, among the most urgent ones by default.
+    pub fn start_strategy(mut self, start_strategy: StartStrategy) -> Self {
+        self.start_strategy = start_strategy;
+        self
+    }
+
+    /// Which weeks of the schedule are optimised, only the first one by default.
+    pub fn objective_mode(mut self, objective_mode: ObjectiveMode) -> Self {
+        self.objective_mode = objective_mode;
+        self
+    }
+
+    /// Warm start, edges of surgeries missing from the instance are ignored.
+    pub fn initial_pheromones(mut self, initial_pheromones: PheromoneMap) -> Self {
+        self.initial_pheromones = initial_pheromones;
+        self
+    }
+
+    /// Saves the pheromones to the file every `interval` rounds, the final ones are on the
+    /// solution.
+    pub fn save_pheromones<P: Into<PathBuf>>(mut self, pheromones_file: P, interval: u32) -> Self {
+        self.pheromones_file = Some(pheromones_file.into());
+        self.pheromones_interval = interval;
+        self
+    }
+
+    /// Saves a checkpoint to the file every `interval` rounds, see `resume_from`.
+    pub fn checkpoint<P: Into<PathBuf>>(mut self, checkpoint_file: P, interval: u32) -> Self {
+        self.checkpoint_file = Some(checkpoint_file.into());
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Carries on the execution saved on the checkpoint from its next round, it must come from
+    /// the same instance and parameters.
+    pub fn resume_from(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+
+    /// Rounds stop as soon as the flag is set, keeping the best schedule found so far, e.g. from a
+    /// signal handler.
+    pub fn stop_flag(mut self, stop_flag: Arc<AtomicBool>) -> Self {
+        self.stop_flag = Some(stop_flag);
+        self
+    }
+
+    /// Rounds stop once the execution runs for this long, checked at the end of each round.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Seed the ants seeds of every round are derived from, so the execution can be replayed.
+    /// Random when absent.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Loads a `.json` file, or a TOML file otherwise, as every configuration file of the solver.
+pub(crate) fn load<T: DeserializeOwned, P: AsRef<Path>>(filename: P) -> Result<T, SolverError> {
+    let content = fs::read_to_string(&filename)?;
+    let is_json = filename
+        .as_ref()
+        .extension()
+        .is_some_and(|extension| extension == "json");
+
+    if is_json {
+        serde_json::from_str(&content).map_err(|error| SolverError::Config(error.to_string()))
+    } else {
+        toml::from_str(&content).map_err(|error| SolverError::Config(error.to_string()))
+    }
+}
@@ -0,0 +1,207 @@
+use crate::solver::surgery::{Priority, Speciality};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum SolverError {
+    Io(io::Error),
+    Parse {
+        line: u64,
+        column: Option<u64>,
+        message: String,
+    },
+    UnknownPriority {
+        surgery_id: usize,
+        priority: Priority,
+    },
+    SurgeryTooLong {
+        surgery_id: usize,
+        duration: u8,
+    },
+    NoRoomForSpeciality {
+        surgery_id: usize,
+        speciality: Speciality,
+    },
+    EmptyInstance,
+    CapacityExceeded {
+        surgery_id: usize,
+        reason: &'static str,
+    },
+    ThreadFailure(String),
+    Config(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Io(error) => write!(f, "I/O error: {}", error),
+            SolverError::Parse {
+                line,
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "Malformed line error at line {}, column {}: {}",
+                line, column, message
+            ),
+            SolverError::Parse {
+                line,
+                column: None,
+                message,
+            } => write!(f, "Malformed line error at line {}: {}", line, message),
+            SolverError::UnknownPriority {
+                surgery_id,
+                priority,
+            } => write!(
+                f,
+                "Surgery {} has priority {} which is not on the priority policy",
+                surgery_id, priority
+            ),
+            SolverError::SurgeryTooLong {
+                surgery_id,
+                duration,
+            } => write!(
+                f,
+                "Surgery {} lasts {} slots and can't fit in a single day",
+                surgery_id, duration
+            ),
+            SolverError::NoRoomForSpeciality {
+                surgery_id,
+                speciality,
+            } => write!(
+                f,
+                "Surgery {} has speciality {} which no room supports",
+                surgery_id, speciality
+            ),
+            SolverError::EmptyInstance => write!(f, "Unable to solve for a empty set of surgeries"),
+            SolverError::CapacityExceeded { surgery_id, reason } => {
+                write!(f, "Tried to allocate surgery {}: {}", surgery_id, reason)
+            }
+            SolverError::ThreadFailure(message) => write!(f, "Ant thread failure: {}", message),
+            SolverError::Config(message) => write!(f, "Invalid configuration: {}", message),
+        }
+    }
+}
+
+impl SolverError {
+    /// The csv crate miscounts lines on files with `\r\n` endings, so lines are counted again
+    /// from the byte offset of the error.
+    pub(crate) fn from_csv_error(error: csv::Error, content: &[u8]) -> Self {
+        let line = error
+            .position()
+            .map(|position| line_at(content, position.byte()));
+        let mut converted = SolverError::from(error);
+        if let (
+            SolverError::Parse {
+                line: error_line, ..
+            },
+            Some(line),
+        ) = (&mut converted, line)
+        {
+            *error_line = line;
+        }
+
+        converted
+    }
+}
+
+/// 1-based line of the record starting at the given byte offset, which may still point at the
+/// `\n` of a `\r\n` terminator.
+pub(crate) fn line_at(content: &[u8], byte: u64) -> u64 {
+    let mut start = (byte as usize).min(content.len());
+    while start < content.len() && (content[start] == b'\r' || content[start] == b'\n') {
+        start += 1;
+    }
+    content[..start]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count() as u64
+        + 1
+}
+
+impl Error for SolverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolverError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SolverError {
+    fn from(error: io::Error) -> Self {
+        SolverError::Io(error)
+    }
+}
+
+impl From<csv::Error> for SolverError {
+    fn from(error: csv::Error) -> Self {
+        let line = error.position().map_or(0, |position| position.line());
+        // The csv crate embeds its own record position on these messages, which is wrong on
+        // `\r\n` files, so they are built from the error kind instead
+        let message = match error.kind() {
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => format!("found {} fields, expected {}", len, expected_len),
+            csv::ErrorKind::Utf8 { err, .. } => err.to_string(),
+            _ => error.to_string(),
+        };
+        match error.into_kind() {
+            csv::ErrorKind::Io(error) => SolverError::Io(error),
+            csv::ErrorKind::Deserialize { err, .. } => SolverError::Parse {
+                line,
+                column: err.field().map(|field| field + 1),
+                message: err.kind().to_string(),
+            },
+            _ => SolverError::Parse {
+                line,
+                column: None,
+                message,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_at_counts_lines_on_crlf_files() {
+        let content = b"header\r\n1,2\r\n3,4\r\n";
+
+        assert_eq!(line_at(content, 0), 1);
+        assert_eq!(line_at(content, 8), 2);
+        // Offset of the `\n` of the previous `\r\n` terminator
+        assert_eq!(line_at(content, 12), 3);
+        assert_eq!(line_at(content, 13), 3);
+        assert_eq!(line_at(content, content.len() as u64), 4);
+    }
+
+    #[test]
+    fn line_at_counts_lines_on_lf_files() {
+        let content = b"header\n1,2\n3,4\n";
+
+        assert_eq!(line_at(content, 7), 2);
+        assert_eq!(line_at(content, 11), 3);
+    }
+
+    #[test]
+    fn csv_errors_on_crlf_files_have_the_right_line_only() {
+        let content = b"a,b\r\n1,2\r\n3,4\r\n5,6\r\n7,8\r\n9\r\n";
+        let error = csv::Reader::from_reader(&content[..])
+            .records()
+            .find_map(Result::err)
+            .unwrap();
+
+        match SolverError::from_csv_error(error, content) {
+            SolverError::Parse { line, message, .. } => {
+                assert_eq!(line, 6);
+                assert!(!message.contains("line"), "{}", message);
+                assert!(!message.contains("record"), "{}", message);
+            }
+            error => panic!("unexpected error {}", error),
+        }
+    }
+}
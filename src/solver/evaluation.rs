@@ -0,0 +1,775 @@
+use crate::solver::config::SolverConfig;
+use crate::solver::instance::Instance;
+use crate::solver::rooms::RoomID;
+use crate::solver::solution::Assignment;
+use crate::solver::surgeon::{SurgeonDaily, SurgeonID};
+use crate::solver::surgery::{Speciality, Surgery};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+/// A rule of the problem broken by a schedule.
+///
+/// Slots are numbered from 1 like on the `sample_data` solutions: a surgery starting at slot `t`
+/// takes its `duration` slots and then the cleaning slots of its speciality, which must all be
+/// within the room day.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    UnknownSurgery {
+        surgery_id: usize,
+    },
+    DuplicateAssignment {
+        surgery_id: usize,
+    },
+    /// Day 0 or a day after the last week of the planning horizon.
+    OutsideHorizon {
+        surgery_id: usize,
+        day: u32,
+    },
+    /// A holiday of the planning horizon.
+    Holiday {
+        surgery_id: usize,
+        day: u32,
+    },
+    /// A room closed by the room catalog, or without a block of the master schedule on the day.
+    RoomUnavailable {
+        surgery_id: usize,
+        day: u32,
+        room: RoomID,
+    },
+    /// A speciality the catalog room or the block of the room doesn't take.
+    SpecialityNotAllowed {
+        surgery_id: usize,
+        day: u32,
+        room: RoomID,
+        speciality: Speciality,
+    },
+    /// Surgery and cleaning slots outside the availability windows of the surgeon on the day,
+    /// which has none on a day off.
+    OutsideSurgeonAvailability {
+        surgery_id: usize,
+        day: u32,
+        surgeon_id: SurgeonID,
+    },
+    PastLastSlot {
+        surgery_id: usize,
+        day: u32,
+        room: RoomID,
+        last_slot: u16,
+    },
+    RoomOverlap {
+        day: u32,
+        room: RoomID,
+        first_surgery_id: usize,
+        second_surgery_id: usize,
+    },
+    MissingCleaning {
+        day: u32,
+        room: RoomID,
+        first_surgery_id: usize,
+        second_surgery_id: usize,
+        cleaning_slots: u16,
+    },
+    SurgeonDoubleBooked {
+        day: u32,
+        surgeon_id: SurgeonID,
+        first_surgery_id: usize,
+        second_surgery_id: usize,
+    },
+    SurgeonDayCap {
+        day: u32,
+        surgeon_id: SurgeonID,
+        slots: u32,
+        max_day_time: u8,
+    },
+    SurgeonWeekCap {
+        /// Week numbered from 1.
+        week: u32,
+        surgeon_id: SurgeonID,
+        slots: u32,
+        max_week_time: u8,
+    },
+    MixedSpecialities {
+        day: u32,
+        room: RoomID,
+        specialities: Vec<Speciality>,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownSurgery { surgery_id } => {
+                write!(f, "surgery {} is not on the instance", surgery_id)
+            }
+            Violation::DuplicateAssignment { surgery_id } => {
+                write!(f, "surgery {} is scheduled more than once", surgery_id)
+            }
+            Violation::OutsideHorizon { surgery_id, day } => write!(
+                f,
+                "surgery {} is on day {}, outside the planning horizon",
+                surgery_id, day
+            ),
+            Violation::Holiday { surgery_id, day } => {
+                write!(f, "surgery {} is on day {}, a holiday", surgery_id, day)
+            }
+            Violation::RoomUnavailable {
+                surgery_id,
+                day,
+                room,
+            } => write!(
+                f,
+                "surgery {} is on room {} on day {}, when the room is closed or has no block",
+                surgery_id, room, day
+            ),
+            Violation::SpecialityNotAllowed {
+                surgery_id,
+                day,
+                room,
+                speciality,
+            } => write!(
+                f,
+                "surgery {} is on room {} on day {}, which doesn't take speciality {}",
+                surgery_id, room, day, speciality
+            ),
+            Violation::OutsideSurgeonAvailability {
+                surgery_id,
+                day,
+                surgeon_id,
+            } => write!(
+                f,
+                "surgery {} on day {} is outside the availability of surgeon {}",
+                surgery_id, day, surgeon_id
+            ),
+            Violation::PastLastSlot {
+                surgery_id,
+                day,
+                room,
+                last_slot,
+            } => write!(
+                f,
+                "surgery {} on day {}, room {} takes up to slot {} with its cleaning, past the end of the day",
+                surgery_id, day, room, last_slot
+            ),
+            Violation::RoomOverlap {
+                day,
+                room,
+                first_surgery_id,
+                second_surgery_id,
+            } => write!(
+                f,
+                "surgeries {} and {} overlap on day {}, room {}",
+                first_surgery_id, second_surgery_id, day, room
+            ),
+            Violation::MissingCleaning {
+                day,
+                room,
+                first_surgery_id,
+                second_surgery_id,
+                cleaning_slots,
+            } => write!(
+                f,
+                "surgery {} starts {} slot(s) after surgery {} ends on day {}, room {}, without its full cleaning",
+                second_surgery_id, cleaning_slots, first_surgery_id, day, room
+            ),
+            Violation::SurgeonDoubleBooked {
+                day,
+                surgeon_id,
+                first_surgery_id,
+                second_surgery_id,
+            } => write!(
+                f,
+                "surgeon {} operates surgeries {} and {} at the same time on day {}",
+                surgeon_id, first_surgery_id, second_surgery_id, day
+            ),
+            Violation::SurgeonDayCap {
+                day,
+                surgeon_id,
+                slots,
+                max_day_time,
+            } => write!(
+                f,
+                "surgeon {} operates {} slots on day {}, more than the {} allowed",
+                surgeon_id, slots, day, max_day_time
+            ),
+            Violation::SurgeonWeekCap {
+                week,
+                surgeon_id,
+                slots,
+                max_week_time,
+            } => write!(
+                f,
+                "surgeon {} operates {} slots on week {}, more than the {} allowed",
+                surgeon_id, slots, week, max_week_time
+            ),
+            Violation::MixedSpecialities {
+                day,
+                room,
+                specialities,
+            } => write!(
+                f,
+                "room {} has surgeries of specialities {:?} on day {}",
+                room, specialities, day
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationReport {
+    pub violations: Vec<Violation>,
+    /// Surgeries of the instance missing from the schedule, which is allowed.
+    pub unscheduled: Vec<usize>,
+}
+
+impl EvaluationReport {
+    pub fn is_feasible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.violations.is_empty() {
+            write!(f, "No violations found")?;
+        } else {
+            write!(f, "Found {} violation(s):", self.violations.len())?;
+            for violation in &self.violations {
+                write!(f, "\n{}", violation)?;
+            }
+        }
+        if !self.unscheduled.is_empty() {
+            write!(f, "\nUnscheduled surgeries: {:?}", self.unscheduled)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A surgery with the slots it was given, in 1 based slot numbers and wider than the `u8` slots
+/// of the solver, so broken schedules can't overflow them.
+struct Placed<'a> {
+    surgery: &'a Surgery,
+    start: u16,
+    // End of the surgery itself, the room is cleaned after it
+    end: u16,
+    cleaning_end: u16,
+}
+
+/// Checks every rule of the problem on a schedule, like the ones read by
+/// `solution::assignments_from_csv`, no matter if it came from this solver or not.
+///
+/// Weekends are the only days the solver avoids that aren't audited, since the `sample_data`
+/// solutions number working days one after the other.
+pub fn evaluate(
+    instance: &Instance,
+    assignments: &[Assignment],
+    config: &SolverConfig,
+) -> EvaluationReport {
+    let resources = &config.resources;
+    let last_day = config.horizon.weeks.map(|weeks| 7 * weeks as u32);
+    let mut report = EvaluationReport::default();
+    let surgeries_by_id = instance
+        .surgeries()
+        .iter()
+        .map(|surgery| (surgery.id, surgery))
+        .collect::<HashMap<_, _>>();
+
+    let mut seen_ids = BTreeSet::new();
+    let mut room_days = BTreeMap::<(u32, RoomID), Vec<Placed>>::new();
+    let mut surgeon_days = BTreeMap::<(u32, SurgeonID), Vec<(usize, u16, u16)>>::new();
+    for assignment in assignments {
+        let surgery = match surgeries_by_id.get(&assignment.surgery_id) {
+            Some(surgery) => *surgery,
+            None => {
+                report.violations.push(Violation::UnknownSurgery {
+                    surgery_id: assignment.surgery_id,
+                });
+                continue;
+            }
+        };
+        if !seen_ids.insert(surgery.id) {
+            report.violations.push(Violation::DuplicateAssignment {
+                surgery_id: surgery.id,
+            });
+            continue;
+        }
+        // Days count from 1, so the other rules can't even tell the week of this one
+        if assignment.day == 0 || last_day.is_some_and(|last_day| assignment.day > last_day) {
+            report.violations.push(Violation::OutsideHorizon {
+                surgery_id: surgery.id,
+                day: assignment.day,
+            });
+            continue;
+        }
+
+        if config.horizon.is_holiday(assignment.day) {
+            report.violations.push(Violation::Holiday {
+                surgery_id: surgery.id,
+                day: assignment.day,
+            });
+        }
+        check_room(surgery, assignment, config, &mut report);
+
+        let start = u16::from(assignment.start);
+        let end = start + u16::from(surgery.duration);
+        let cleaning_end = end + u16::from(resources.cleaning_slots(surgery.speciality));
+        // A surgery without any slot ends before it starts
+        let last_slot = cleaning_end.saturating_sub(1);
+        if start == 0 || last_slot > u16::from(resources.slots_per_day) {
+            report.violations.push(Violation::PastLastSlot {
+                surgery_id: surgery.id,
+                day: assignment.day,
+                room: assignment.room,
+                last_slot,
+            });
+        }
+        let windows = resources
+            .surgeon_day(surgery.surgeon_id, assignment.day)
+            .and_then(|availability| availability.windows());
+        let inside_windows = |windows: Vec<Range<u8>>| {
+            windows.iter().any(|window| {
+                u16::from(window.start) <= start && cleaning_end <= u16::from(window.end)
+            })
+        };
+        if !windows.is_none_or(inside_windows) {
+            report
+                .violations
+                .push(Violation::OutsideSurgeonAvailability {
+                    surgery_id: surgery.id,
+                    day: assignment.day,
+                    surgeon_id: surgery.surgeon_id,
+                });
+        }
+
+        room_days
+            .entry((assignment.day, assignment.room))
+            .or_default()
+            .push(Placed {
+                surgery,
+                start,
+                end,
+                cleaning_end,
+            });
+        surgeon_days
+            .entry((assignment.day, surgery.surgeon_id))
+            .or_default()
+            .push((surgery.id, start, end));
+    }
+
+    for (&(day, room), placed) in &mut room_days {
+        check_room_day(day, room, placed, &mut report);
+    }
+
+    let mut surgeon_weeks = BTreeMap::<(u32, SurgeonID), u32>::new();
+    for (&(day, surgeon_id), surgeries) in &mut surgeon_days {
+        surgeries.sort_by_key(|&(_, start, _)| start);
+        for (index, &(first_surgery_id, _, first_end)) in surgeries.iter().enumerate() {
+            for &(second_surgery_id, second_start, _) in &surgeries[index + 1..] {
+                if second_start < first_end {
+                    report.violations.push(Violation::SurgeonDoubleBooked {
+                        day,
+                        surgeon_id,
+                        first_surgery_id,
+                        second_surgery_id,
+                    });
+                }
+            }
+        }
+
+        let slots = surgeries
+            .iter()
+            .map(|&(_, start, end)| u32::from(end - start))
+            .sum::<u32>();
+        let max_day_time = SurgeonDaily::for_day(surgeon_id, resources, day).max_day_time();
+        if slots > u32::from(max_day_time) {
+            report.violations.push(Violation::SurgeonDayCap {
+                day,
+                surgeon_id,
+                slots,
+                max_day_time,
+            });
+        }
+        *surgeon_weeks
+            .entry(((day - 1) / 7 + 1, surgeon_id))
+            .or_default() += slots;
+    }
+
+    for ((week, surgeon_id), slots) in surgeon_weeks {
+        let max_week_time = resources.surgeon_max_week_time(surgeon_id);
+        if slots > u32::from(max_week_time) {
+            report.violations.push(Violation::SurgeonWeekCap {
+                week,
+                surgeon_id,
+                slots,
+                max_week_time,
+            });
+        }
+    }
+
+    report.unscheduled = surgeries_by_id
+        .keys()
+        .filter(|id| !seen_ids.contains(id))
+        .cloned()
+        .collect();
+    report.unscheduled.sort_unstable();
+
+    report
+}
+
+/// Rooms of the catalog and blocks of the master schedule, without any every room is open to
+/// every speciality.
+fn check_room(
+    surgery: &Surgery,
+    assignment: &Assignment,
+    config: &SolverConfig,
+    report: &mut EvaluationReport,
+) {
+    let resources = &config.resources;
+    let (day, room) = (assignment.day, assignment.room);
+    let allows_speciality = if resources.has_blocks() {
+        match resources
+            .blocks_on_day(day)
+            .into_iter()
+            .find(|block| block.room == room)
+        {
+            Some(block) => block.speciality == surgery.speciality,
+            None => {
+                report.violations.push(Violation::RoomUnavailable {
+                    surgery_id: surgery.id,
+                    day,
+                    room,
+                });
+                return;
+            }
+        }
+    } else if !resources.rooms.is_empty() {
+        match resources
+            .rooms_on_day(config.rooms_count, day)
+            .into_iter()
+            .find(|open_room| open_room.id == room)
+        {
+            Some(open_room) => open_room.allows(surgery.speciality),
+            None => {
+                report.violations.push(Violation::RoomUnavailable {
+                    surgery_id: surgery.id,
+                    day,
+                    room,
+                });
+                return;
+            }
+        }
+    } else {
+        true
+    };
+
+    if !allows_speciality {
+        report.violations.push(Violation::SpecialityNotAllowed {
+            surgery_id: surgery.id,
+            day,
+            room,
+            speciality: surgery.speciality,
+        });
+    }
+}
+
+fn check_room_day(day: u32, room: RoomID, placed: &mut [Placed], report: &mut EvaluationReport) {
+    let specialities = placed
+        .iter()
+        .map(|placed| placed.surgery.speciality)
+        .collect::<BTreeSet<_>>();
+    if specialities.len() > 1 {
+        report.violations.push(Violation::MixedSpecialities {
+            day,
+            room,
+            specialities: specialities.into_iter().collect(),
+        });
+    }
+
+    placed.sort_by_key(|placed| placed.start);
+    for (index, first) in placed.iter().enumerate() {
+        for second in &placed[index + 1..] {
+            if second.start < first.end {
+                report.violations.push(Violation::RoomOverlap {
+                    day,
+                    room,
+                    first_surgery_id: first.surgery.id,
+                    second_surgery_id: second.surgery.id,
+                });
+            } else if second.start < first.cleaning_end {
+                report.violations.push(Violation::MissingCleaning {
+                    day,
+                    room,
+                    first_surgery_id: first.surgery.id,
+                    second_surgery_id: second.surgery.id,
+                    cleaning_slots: second.start - first.end,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::availability::DayAvailability;
+    use crate::solver::blocks::Block;
+    use crate::solver::horizon::Horizon;
+    use crate::solver::resources::ResourceConfig;
+    use crate::solver::rooms::Room;
+    use crate::solver::solution;
+    use crate::solver::week::placement::PlacementStrategy;
+    use crate::solver::Solver;
+    use chrono::NaiveDate;
+
+    /// Surgeries of every priority and two specialities, some long enough to end their day.
+    fn instance() -> Instance {
+        Instance::from_surgeries(
+            (1..=30)
+                .map(|id| {
+                    let duration = (id * 7 % 20 + 3) as u8;
+                    Surgery::new(
+                        id,
+                        duration,
+                        id as u32,
+                        id % 4 + 1,
+                        (id % 2 + 1) as u32,
+                        id % 4 + 1,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn solver_schedules_break_no_rule() {
+        let instance = instance();
+        for placement in [
+            PlacementStrategy::Append,
+            PlacementStrategy::FirstFit,
+            PlacementStrategy::BestFit,
+        ] {
+            let config = SolverConfig::new()
+                .threads_count(1)
+                .ants_count(2)
+                .rooms_count(2)
+                .max_rounds_count(3)
+                .placement_strategy(placement)
+                .seed(1);
+            let solution = Solver::solve(&instance, &config).unwrap();
+            let mut csv = Vec::new();
+            solution.write_csv(&mut csv).unwrap();
+            let assignments = solution::assignments_from_reader(csv.as_slice()).unwrap();
+
+            let report = evaluate(&instance, &assignments, &config);
+            assert!(!assignments.is_empty());
+            assert!(report.is_feasible(), "{} with {}", report, placement);
+            assert_eq!(assignments.len() + report.unscheduled.len(), 30);
+        }
+    }
+
+    fn assignment(surgery_id: usize, day: u32, room: RoomID, start: u8) -> Assignment {
+        Assignment {
+            surgery_id,
+            room,
+            day,
+            date: None,
+            start,
+        }
+    }
+
+    /// Room 2 only takes speciality 2 and room 3 is closed on day 3, surgeon 1 operates up to
+    /// slot 20 on day 1 and surgeon 2 is off on day 2, and day 4 is a holiday.
+    fn calendar_config() -> SolverConfig {
+        let room = |id, specialities: Vec<Speciality>, closed_days| Room {
+            id,
+            specialities,
+            closed_days,
+        };
+        let availability = |surgeon, day, windows| DayAvailability {
+            surgeon,
+            day,
+            windows: Some(windows),
+            max_day_time: None,
+        };
+
+        SolverConfig::new()
+            .resources(ResourceConfig {
+                rooms: vec![
+                    room(1, vec![], vec![]),
+                    room(2, vec![2], vec![]),
+                    room(3, vec![], vec![3]),
+                ],
+                availability: vec![
+                    availability(1, 1, vec![(1, 20)]),
+                    availability(2, 2, vec![]),
+                ],
+                ..ResourceConfig::default()
+            })
+            .horizon(Horizon {
+                start_date: NaiveDate::from_ymd_opt(2024, 1, 1),
+                holidays: vec![NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()],
+                ..Horizon::default()
+            })
+    }
+
+    fn blocks_config() -> SolverConfig {
+        let blocks = (1..=5)
+            .flat_map(|weekday| {
+                (1..=2).map(move |room| Block {
+                    weekday,
+                    room,
+                    speciality: (weekday + room as u32) % 2 + 1,
+                })
+            })
+            .collect();
+
+        SolverConfig::new().resources(ResourceConfig {
+            blocks,
+            ..ResourceConfig::default()
+        })
+    }
+
+    #[test]
+    fn solver_schedules_respect_rooms_calendars_and_blocks() {
+        let instance = instance();
+        for config in [calendar_config(), blocks_config()] {
+            let config = config
+                .threads_count(1)
+                .ants_count(2)
+                .rooms_count(2)
+                .max_rounds_count(3)
+                .seed(1);
+            let solution = Solver::solve(&instance, &config).unwrap();
+
+            let report = evaluate(&instance, &solution.assignments, &config);
+            assert!(!solution.assignments.is_empty());
+            assert!(report.is_feasible(), "{}", report);
+        }
+    }
+
+    #[test]
+    fn rooms_calendars_and_holidays_are_audited() {
+        let assignments = [
+            assignment(1, 2, 1, 30),
+            assignment(2, 1, 2, 1),
+            assignment(3, 3, 3, 1),
+            assignment(4, 1, 1, 15),
+            assignment(5, 4, 1, 1),
+            assignment(7, 2, 1, 1),
+        ];
+
+        let report = evaluate(&instance(), &assignments, &calendar_config());
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::OutsideSurgeonAvailability {
+                    surgery_id: 1,
+                    day: 2,
+                    surgeon_id: 2
+                },
+                Violation::SpecialityNotAllowed {
+                    surgery_id: 2,
+                    day: 1,
+                    room: 2,
+                    speciality: 1
+                },
+                Violation::RoomUnavailable {
+                    surgery_id: 3,
+                    day: 3,
+                    room: 3
+                },
+                Violation::OutsideSurgeonAvailability {
+                    surgery_id: 4,
+                    day: 1,
+                    surgeon_id: 1
+                },
+                Violation::Holiday {
+                    surgery_id: 5,
+                    day: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_are_audited() {
+        // Blocks of weekday 1 are for speciality 1 on room 1 and 2 on room 2
+        let assignments = [
+            assignment(2, 1, 1, 1),
+            assignment(1, 8, 1, 1),
+            assignment(3, 2, 3, 1),
+        ];
+
+        let report = evaluate(&instance(), &assignments, &blocks_config());
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::SpecialityNotAllowed {
+                    surgery_id: 1,
+                    day: 8,
+                    room: 1,
+                    speciality: 2
+                },
+                Violation::RoomUnavailable {
+                    surgery_id: 3,
+                    day: 2,
+                    room: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn surgeries_without_slots_start_on_a_slot() {
+        let instance = Instance::from_surgeries(vec![Surgery::new(1, 0, 0, 1, 1, 1)]);
+        let config = SolverConfig::new().resources(ResourceConfig {
+            cleaning_slots: 0,
+            ..ResourceConfig::default()
+        });
+
+        let report = evaluate(&instance, &[assignment(1, 1, 1, 0)], &config);
+        assert_eq!(
+            report.violations,
+            vec![Violation::PastLastSlot {
+                surgery_id: 1,
+                day: 1,
+                room: 1,
+                last_slot: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn days_outside_the_horizon_are_violations() {
+        let instance = instance();
+        let config = SolverConfig::new();
+        let assignment = |surgery_id, day| Assignment {
+            surgery_id,
+            room: 1,
+            day,
+            date: None,
+            start: 1,
+        };
+
+        let report = evaluate(&instance, &[assignment(1, 0), assignment(2, 8)], &config);
+        assert_eq!(
+            report.violations,
+            vec![Violation::OutsideHorizon {
+                surgery_id: 1,
+                day: 0
+            }]
+        );
+
+        let mut horizon = config.horizon.clone();
+        horizon.weeks = Some(1);
+        let report = evaluate(&instance, &[assignment(2, 8)], &config.horizon(horizon));
+        assert_eq!(
+            report.violations,
+            vec![Violation::OutsideHorizon {
+                surgery_id: 2,
+                day: 8
+            }]
+        );
+    }
+}
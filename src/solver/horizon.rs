@@ -0,0 +1,155 @@
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::path::Path;
+
+/// Weeks covered by the schedule and which of their days have surgeries.
+///
+/// Days are numbered from 1 on the first day of the first week, counting every calendar day,
+/// so the day 8 is always the first day of the second week.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Horizon {
+    /// Weeks to plan, surgeries that don't fit on them are left unscheduled. As many weeks as
+    /// needed to schedule every surgery when absent.
+    pub weeks: Option<usize>,
+    /// Days with surgeries at the start of each week, the remaining ones are the weekend.
+    pub working_days: u32,
+    /// Date of the first day of the first week, usually a monday.
+    pub start_date: Option<NaiveDate>,
+    /// Working days without surgeries, requires a start date.
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for Horizon {
+    fn default() -> Self {
+        Self {
+            weeks: None,
+            working_days: 5,
+            start_date: None,
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl Horizon {
+    /// Missing fields keep their defaults.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Self, SolverError> {
+        config::load(filename)
+    }
+
+    pub fn check(&self) -> Result<(), SolverError> {
+        if !(1..=7).contains(&self.working_days) {
+            return Err(SolverError::Config(format!(
+                "weeks have between 1 and 7 working days, got {}",
+                self.working_days
+            )));
+        }
+        if self.weeks == Some(0) {
+            return Err(SolverError::Config(
+                "planning horizon must have at least one week".to_string(),
+            ));
+        }
+        if self.start_date.is_none() && !self.holidays.is_empty() {
+            return Err(SolverError::Config(
+                "holidays need the start date of the planning horizon".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The same calendar on a schedule starting `weeks` weeks later.
+    pub fn shifted(&self, weeks: usize) -> Self {
+        Self {
+            start_date: self
+                .start_date
+                .map(|start_date| start_date + Duration::weeks(weeks as i64)),
+            ..self.clone()
+        }
+    }
+
+    /// Numbers of the days with surgeries on the given week.
+    pub fn working_days_of_week(&self, week_index: usize) -> Vec<u32> {
+        (1..=self.working_days)
+            .map(|weekday| 7 * week_index as u32 + weekday)
+            .filter(|&day| !self.is_holiday(day))
+            .collect()
+    }
+
+    /// Whether weeks after the given count of planned weeks still belong to the horizon.
+    pub fn has_week_after(&self, weeks_count: usize) -> bool {
+        self.weeks.is_none_or(|weeks| weeks_count < weeks)
+    }
+
+    pub fn date(&self, day: u32) -> Option<NaiveDate> {
+        self.start_date
+            .map(|start_date| start_date + Duration::days(i64::from(day) - 1))
+    }
+
+    /// Number of the last holiday, 0 without any.
+    pub fn last_holiday(&self) -> u32 {
+        match self.start_date {
+            Some(start_date) => self
+                .holidays
+                .iter()
+                .map(|&holiday| (holiday - start_date).num_days() + 1)
+                .filter_map(|day| u32::try_from(day).ok())
+                .max()
+                .unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    pub fn is_holiday(&self, day: u32) -> bool {
+        self.date(day)
+            .is_some_and(|date| self.holidays.contains(&date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn second_week_starts_on_day_8() {
+        let horizon = Horizon::default();
+
+        assert_eq!(horizon.working_days_of_week(0), vec![1, 2, 3, 4, 5]);
+        assert_eq!(horizon.working_days_of_week(1), vec![8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn holidays_are_skipped() {
+        // 2024-01-01 is a monday
+        let horizon = Horizon {
+            start_date: Some(date(1)),
+            holidays: vec![date(3), date(8)],
+            ..Horizon::default()
+        };
+
+        assert_eq!(horizon.working_days_of_week(0), vec![1, 2, 4, 5]);
+        assert_eq!(horizon.working_days_of_week(1), vec![9, 10, 11, 12]);
+        assert_eq!(horizon.last_holiday(), 8);
+    }
+
+    #[test]
+    fn days_are_dated_from_the_start_date() {
+        let horizon = Horizon {
+            start_date: Some(date(1)),
+            ..Horizon::default()
+        };
+
+        assert_eq!(horizon.date(1), Some(date(1)));
+        assert_eq!(horizon.date(8), Some(date(8)));
+        assert_eq!(horizon.date(12), Some(date(12)));
+        assert_eq!(horizon.shifted(1).date(1), Some(date(8)));
+        assert_eq!(Horizon::default().date(1), None);
+    }
+}
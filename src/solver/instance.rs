@@ -0,0 +1,114 @@
+use crate::solver::error::{line_at, SolverError};
+use crate::solver::surgeon::SurgeonID;
+use crate::solver::surgery::{DaysWaiting, Priority, Speciality, Surgery};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The waiting list to be scheduled.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    surgeries: HashSet<Surgery>,
+    surgeons_ids: Vec<SurgeonID>,
+}
+
+impl Instance {
+    /// Surgeries sharing an id with an earlier one are dropped, `Instance::from_reader` rejects
+    /// them and `validation::validate_surgeries` reports them.
+    pub fn from_surgeries(surgeries: Vec<Surgery>) -> Self {
+        let surgeons_ids = surgeries.iter().map(|surgery| surgery.surgeon_id).collect();
+
+        Self {
+            surgeries: surgeries.into_iter().collect(),
+            surgeons_ids,
+        }
+    }
+
+    pub fn from_csv<P: AsRef<Path>>(filename: P) -> Result<Self, SolverError> {
+        Self::from_reader(File::open(filename)?)
+    }
+
+    /// Reads an instance on the same comma separated format of the csv files on `sample_data`.
+    /// A surgery id used twice is an error, since one of the surgeries would be lost.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, SolverError> {
+        // Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)
+        type Record = (usize, Priority, DaysWaiting, Speciality, SurgeonID, u8);
+
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+
+        let mut first_lines = HashMap::new();
+        let surgeries = csv::Reader::from_reader(content.as_slice())
+            .records()
+            .map(|row| {
+                let record = row.map_err(|error| SolverError::from_csv_error(error, &content))?;
+                let line = record
+                    .position()
+                    .map_or(0, |position| line_at(&content, position.byte()));
+                let (id, priority, days_waiting, speciality, surgeon_id, duration): Record = record
+                    .deserialize(None)
+                    .map_err(|error| match SolverError::from(error) {
+                        SolverError::Parse {
+                            column, message, ..
+                        } => SolverError::Parse {
+                            line,
+                            column,
+                            message,
+                        },
+                        error => error,
+                    })?;
+                if let Some(first_line) = first_lines.insert(id, line) {
+                    return Err(SolverError::Parse {
+                        line,
+                        column: Some(1),
+                        message: format!(
+                            "surgery id {} was already used at line {}",
+                            id, first_line
+                        ),
+                    });
+                }
+
+                Ok(Surgery::new(
+                    id,
+                    duration,
+                    days_waiting,
+                    priority,
+                    speciality,
+                    surgeon_id,
+                ))
+            })
+            .collect::<Result<_, SolverError>>()?;
+
+        Ok(Self::from_surgeries(surgeries))
+    }
+
+    pub fn surgeries(&self) -> &HashSet<Surgery> {
+        &self.surgeries
+    }
+
+    pub fn surgeons_ids(&self) -> &[SurgeonID] {
+        &self.surgeons_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_ids_are_rejected_on_their_line() {
+        let content = "Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)\r\n\
+            1,1,10,1,1,5\r\n\
+            2,1,10,1,1,5\r\n\
+            1,2,10,1,1,5\r\n";
+
+        match Instance::from_reader(content.as_bytes()) {
+            Err(SolverError::Parse { line, message, .. }) => {
+                assert_eq!(line, 4);
+                assert_eq!(message, "surgery id 1 was already used at line 2");
+            }
+            result => panic!("unexpected {:?}", result.map(|instance| instance.surgeries)),
+        }
+    }
+}
@@ -1,138 +1,371 @@
-use std::collections::{HashMap, HashSet};
-
 pub mod ant_colony;
+pub mod availability;
+pub mod blocks;
+pub mod breakdown;
+pub mod checkpoint;
+pub mod config;
+pub mod error;
+pub mod evaluation;
+pub mod horizon;
+pub mod instance;
+pub mod objective;
+pub mod pheromones;
+pub mod policy;
+pub mod resources;
+pub mod rooms;
+pub mod simulation;
+pub mod solution;
 pub mod surgeon;
 pub mod surgery;
+pub mod validation;
 pub mod week;
 
-use crate::solver::surgery::Speciality;
-use crate::solver::week::Week;
 use ant_colony::AntColony;
-use std::fmt::Debug;
-use std::path::Path;
+use checkpoint::Checkpoint;
+use config::SolverConfig;
+use error::SolverError;
+use instance::Instance;
+use solution::{Solution, StopReason};
+use std::sync::atomic::Ordering;
 use std::time::{Duration, Instant};
-use surgeon::SurgeonID;
-use surgery::{DaysWaiting, Priority, Surgery};
+use week::Schedule;
 
 pub struct Solver {
     ant_colony: AntColony,
 }
 
 impl Solver {
-    pub fn solve<P: AsRef<Path> + Debug + Copy>(
-        instance_filename: P,
-        threads_count: usize,
-        ants_count: usize,
-        rooms_count: usize,
-        max_days_waiting: HashMap<Priority, DaysWaiting>,
-        priority_penalties: HashMap<Priority, u32>,
-        alpha: f64,
-        beta: f64,
-        elitism_factor: f64,
-        pheromone_deposit_rate: f64,
-        pheromone_evaporation_rate: f64,
-        max_rounds_count: u32,
-        max_rounds_without_improvement: u32,
-        target: f64,
-        intensify_probability: f64,
-    ) -> (f64, u32, Vec<(Week, f64)>, Duration) {
-        let (surgeries, surgeons_ids) = Self::load_from_csv(instance_filename);
-
+    pub fn solve(instance: &Instance, config: &SolverConfig) -> Result<Solution, SolverError> {
         let mut solver = Self {
-            ant_colony: AntColony::new(
-                threads_count,
-                ants_count,
-                rooms_count,
-                alpha,
-                beta,
-                elitism_factor,
-                pheromone_deposit_rate,
-                pheromone_evaporation_rate,
-                surgeons_ids,
-                surgeries,
-                max_days_waiting,
-                priority_penalties,
-                intensify_probability,
-            ),
+            ant_colony: AntColony::new(config, instance)?,
         };
 
         let now = Instant::now();
+        let result = solver.run_rounds(config, now);
+        let pheromones = solver.ant_colony.pheromones();
+        // Ants must be killed even when a round failed
+        let killed = solver.ant_colony.kill_ants();
+        let (
+            best_objective_function_result,
+            best_objective_function_round,
+            best_scheduling,
+            stop_reason,
+        ) = result?;
+        killed?;
 
+        let elapsed_time = now.elapsed() + Self::resumed_elapsed_time(config);
+        // Nothing better than the checkpoint was found, so its schedule is still the best one
+        Ok(match &config.resume {
+            Some(checkpoint) if best_scheduling.is_empty() => {
+                Solution::from_checkpoint(checkpoint, elapsed_time, pheromones, stop_reason)
+            }
+            _ => Solution::new(
+                best_objective_function_result,
+                best_objective_function_round,
+                best_scheduling,
+                elapsed_time,
+                pheromones,
+                stop_reason,
+            ),
+        })
+    }
+
+    fn resumed_elapsed_time(config: &SolverConfig) -> Duration {
+        config
+            .resume
+            .as_ref()
+            .map_or(Duration::default(), |checkpoint| checkpoint.elapsed_time)
+    }
+
+    fn run_rounds(
+        &mut self,
+        config: &SolverConfig,
+        started_at: Instant,
+    ) -> Result<(f64, u32, Schedule, StopReason), SolverError> {
+        let target = config.target;
         let mut best_objective_function_result = f64::INFINITY;
         let mut best_objective_function_round = 0;
         let mut best_scheduling = Vec::new();
+        // Also when a resumed execution had already run every round
+        let mut stop_reason = StopReason::MaxRounds;
+        let mut first_round = 1;
+        if let Some(checkpoint) = &config.resume {
+            best_objective_function_result = checkpoint.best_objective;
+            best_objective_function_round = checkpoint.best_round;
+            first_round = checkpoint.round + 1;
+        }
         let range = if target != 0.0 {
-            1..(u32::MAX)
+            first_round..=u32::MAX
         } else {
-            1..(max_rounds_count + 1)
+            first_round..=config.max_rounds_count
         };
 
         for round in range {
-            let (objective_function_result, scheduling, elapsed_time) =
-                solver.ant_colony.round(round);
+            if config
+                .stop_flag
+                .as_ref()
+                .is_some_and(|stop_flag| stop_flag.load(Ordering::Relaxed))
+            {
+                stop_reason = StopReason::Interrupted;
+                // Keeps the last completed round, so the execution can be resumed from it
+                if let Some(checkpoint_file) = &config.checkpoint_file {
+                    if round > first_round {
+                        self.checkpoint(
+                            config,
+                            round - 1,
+                            started_at.elapsed(),
+                            best_objective_function_result,
+                            best_objective_function_round,
+                            &best_scheduling,
+                        )
+                        .save(checkpoint_file)?;
+                    }
+                }
+                break;
+            }
+
+            let (solution, elapsed_time) = self.ant_colony.round()?;
+            let objective_function_result = solution.objective_function_result;
 
             if round % 100 == 0 {
                 println!(
-                    "Round:\t{:5};\tObjective Function:\t{:15};\tElapsed Time:\t{:#?}",
-                    round, objective_function_result, elapsed_time
+                    "Round:\t{:5};\tObjective Function:\t{:15};\tFirst Week:\t{:15};\tTotal Horizon:\t{:15};\tElapsed Time:\t{:#?}",
+                    round,
+                    objective_function_result,
+                    solution.first_week_objective,
+                    solution.total_objective,
+                    elapsed_time
                 );
             }
+            if let Some(pheromones_file) = &config.pheromones_file {
+                if config.pheromones_interval > 0 && round % config.pheromones_interval == 0 {
+                    pheromones::save(&self.ant_colony.pheromones(), pheromones_file)?;
+                }
+            }
             if objective_function_result < best_objective_function_result {
                 best_objective_function_result = objective_function_result;
                 best_objective_function_round = round;
-                best_scheduling = scheduling;
+                best_scheduling = solution.all_weeks_results;
+            }
+            if let Some(checkpoint_file) = &config.checkpoint_file {
+                if config.checkpoint_interval > 0 && round % config.checkpoint_interval == 0 {
+                    self.checkpoint(
+                        config,
+                        round,
+                        started_at.elapsed(),
+                        best_objective_function_result,
+                        best_objective_function_round,
+                        &best_scheduling,
+                    )
+                    .save(checkpoint_file)?;
+                }
             }
             if target == 0.0
-                && round - best_objective_function_round > max_rounds_without_improvement
+                && round - best_objective_function_round > config.max_rounds_without_improvement
             {
+                stop_reason = StopReason::NoImprovement;
                 break;
             }
 
             if objective_function_result <= target {
+                stop_reason = StopReason::Target;
                 break;
             }
-        }
 
-        solver.ant_colony.kill_ants();
+            // A resumed execution also counts the time spent before its checkpoint
+            let total_elapsed_time = started_at.elapsed() + Self::resumed_elapsed_time(config);
+            if config
+                .time_limit
+                .is_some_and(|time_limit| total_elapsed_time >= time_limit)
+            {
+                stop_reason = StopReason::TimeLimit;
+                break;
+            }
+        }
 
-        (
+        Ok((
             best_objective_function_result,
             best_objective_function_round,
             best_scheduling,
-            now.elapsed(),
-        )
+            stop_reason,
+        ))
     }
 
-    pub fn load_from_csv<P: AsRef<Path> + Debug + Copy>(
-        filename: P,
-    ) -> (HashSet<Surgery>, Vec<SurgeonID>) {
-        // Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)
-        type Record = (usize, Priority, DaysWaiting, Speciality, SurgeonID, u8);
-
-        let mut surgeries = HashSet::new();
-        let mut surgeons_ids = Vec::new();
-
-        for (line_number, row) in csv::Reader::from_path(filename)
-            .unwrap_or_else(|_| panic!("Unable to read from {:#?}", &filename))
-            .deserialize()
-            .enumerate()
-        {
-            let record: Record =
-                row.unwrap_or_else(|_| panic!("Malformed line error at line: {}", line_number));
-            let (id, priority, days_waiting, speciality, surgeon_id, duration) = record;
-
-            surgeries.insert(Surgery::new(
-                id,
-                duration,
-                days_waiting,
-                priority,
-                speciality,
-                surgeon_id,
-            ));
-
-            surgeons_ids.push(surgeon_id);
+    /// State after the round, the best schedule comes from the resumed checkpoint until a better
+    /// one is found.
+    fn checkpoint(
+        &self,
+        config: &SolverConfig,
+        round: u32,
+        elapsed_time: Duration,
+        best_objective: f64,
+        best_round: u32,
+        best_scheduling: &Schedule,
+    ) -> Checkpoint {
+        let (best_first_week_objective, best_total_objective, best_assignments) =
+            match &config.resume {
+                Some(checkpoint) if best_scheduling.is_empty() => (
+                    checkpoint.best_first_week_objective,
+                    checkpoint.best_total_objective,
+                    checkpoint.best_assignments.clone(),
+                ),
+                _ => (
+                    objective::first_week_objective(best_scheduling),
+                    objective::total_objective(best_scheduling),
+                    Solution::collect_assignments(best_scheduling),
+                ),
+            };
+
+        Checkpoint {
+            round,
+            master_seed: self.ant_colony.master_seed(),
+            seed: self.ant_colony.seed(),
+            elapsed_time: elapsed_time + Self::resumed_elapsed_time(config),
+            best_round,
+            best_objective,
+            best_first_week_objective,
+            best_total_objective,
+            best_assignments,
+            pheromones: self.ant_colony.raw_pheromones(),
+            untouched_pheromone: self.ant_colony.untouched_pheromone(),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::surgery::Surgery;
+
+    fn instance() -> Instance {
+        Instance::from_surgeries(
+            (1..=12)
+                .map(|id| {
+                    Surgery::new(
+                        id,
+                        6 + 2 * (id % 5) as u8,
+                        10 * id as u32,
+                        id % 4 + 1,
+                        1,
+                        id % 3 + 1,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn config() -> SolverConfig {
+        SolverConfig::new()
+            .threads_count(2)
+            .ants_count(4)
+            .rooms_count(1)
+            .max_rounds_count(30)
+            .seed(42)
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_solution() {
+        let first = Solver::solve(&instance(), &config()).unwrap();
+        let second = Solver::solve(&instance(), &config()).unwrap();
+
+        assert_eq!(first.objective, second.objective);
+        assert_eq!(first.best_round, second.best_round);
+        assert_eq!(first.assignments, second.assignments);
+        assert_eq!(first.pheromones, second.pheromones);
+        assert_eq!(first.stop_reason, second.stop_reason);
+    }
+
+    #[test]
+    fn resumed_execution_matches_the_uninterrupted_one() {
+        let filename =
+            std::env::temp_dir().join(format!("resume_test_{}.json", std::process::id()));
+        // Checkpoints are saved every 7 of the 30 rounds, so the file ends with round 28
+        let uninterrupted = Solver::solve(&instance(), &config().checkpoint(&filename, 7)).unwrap();
+        let checkpoint = Checkpoint::from_file(&filename);
+        std::fs::remove_file(&filename).unwrap();
+        let checkpoint = checkpoint.unwrap();
+        assert_eq!(checkpoint.round, 28);
+
+        let resumed = Solver::solve(&instance(), &config().resume_from(checkpoint)).unwrap();
+
+        assert_eq!(resumed.objective, uninterrupted.objective);
+        assert_eq!(
+            resumed.first_week_objective,
+            uninterrupted.first_week_objective
+        );
+        assert_eq!(resumed.total_objective, uninterrupted.total_objective);
+        assert_eq!(resumed.best_round, uninterrupted.best_round);
+        assert_eq!(resumed.assignments, uninterrupted.assignments);
+        assert_eq!(resumed.pheromones, uninterrupted.pheromones);
+        assert_eq!(resumed.stop_reason, uninterrupted.stop_reason);
+    }
+
+    #[test]
+    fn time_limit_stops_long_executions() {
+        let config = config()
+            .max_rounds_count(u32::MAX)
+            .time_limit(Duration::from_millis(1));
+
+        let solution = Solver::solve(&instance(), &config).unwrap();
+        assert_eq!(solution.stop_reason, StopReason::TimeLimit);
+    }
+
+    #[test]
+    fn time_limit_counts_the_time_before_the_checkpoint() {
+        let filename =
+            std::env::temp_dir().join(format!("time_limit_test_{}.json", std::process::id()));
+        Solver::solve(
+            &instance(),
+            &config().max_rounds_count(2).checkpoint(&filename, 2),
+        )
+        .unwrap();
+        let checkpoint = Checkpoint::from_file(&filename);
+        std::fs::remove_file(&filename).unwrap();
+        let checkpoint = Checkpoint {
+            elapsed_time: Duration::from_secs(3600),
+            ..checkpoint.unwrap()
+        };
+        let config = config()
+            .max_rounds_count(u32::MAX)
+            .time_limit(Duration::from_secs(3600))
+            .resume_from(checkpoint);
+
+        let solution = Solver::solve(&instance(), &config).unwrap();
+        assert_eq!(solution.stop_reason, StopReason::TimeLimit);
+        assert!(solution.elapsed_time >= Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn solutions_agree_with_breakdown_and_evaluation() {
+        let horizon = horizon::Horizon {
+            weeks: Some(2),
+            ..horizon::Horizon::default()
+        };
+        let config = SolverConfig::new()
+            .threads_count(2)
+            .ants_count(4)
+            .rooms_count(1)
+            .horizon(horizon)
+            .max_rounds_count(20)
+            .seed(7);
+        let instance = instance();
+
+        let solution = Solver::solve(&instance, &config).unwrap();
 
-        (surgeries, surgeons_ids)
+        let breakdown = breakdown::breakdown(&instance, &solution.assignments, &config).unwrap();
+        let difference = (breakdown.total() - solution.objective).abs();
+        assert!(
+            difference <= 1e-9 * solution.objective.abs(),
+            "{} != {}",
+            breakdown.total(),
+            solution.objective
+        );
+        assert!(!solution.assignments.is_empty());
+        assert!(solution
+            .assignments
+            .iter()
+            .all(|assignment| (1..=14).contains(&assignment.day)));
+        let report = evaluation::evaluate(&instance, &solution.assignments, &config);
+        assert!(report.is_feasible(), "{}", report);
     }
 }
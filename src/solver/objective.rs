@@ -0,0 +1,82 @@
+use crate::solver::week::Week;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which weeks of a schedule the colony optimises.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ObjectiveMode {
+    /// Only the first week, the one that is going to be performed.
+    #[default]
+    FirstWeek,
+    /// Sum of every week objective.
+    TotalHorizon,
+    /// Sum of every week objective, each week weighing `decay` times the previous one.
+    WeightedHorizon { decay: f64 },
+}
+
+impl ObjectiveMode {
+    pub fn evaluate(&self, weeks: &[(Week, f64)]) -> f64 {
+        weeks
+            .iter()
+            .enumerate()
+            .map(|(week_index, (_, objective))| self.week_weight(week_index) * objective)
+            .fold(0.0, |sum, objective| saturate(sum + objective))
+    }
+
+    /// How much the objective of the given week, numbered from 0, counts on the optimised value.
+    pub fn week_weight(&self, week_index: usize) -> f64 {
+        match self {
+            ObjectiveMode::FirstWeek if week_index == 0 => 1.0,
+            ObjectiveMode::FirstWeek => 0.0,
+            ObjectiveMode::TotalHorizon => 1.0,
+            ObjectiveMode::WeightedHorizon { decay } => decay.powi(week_index as i32),
+        }
+    }
+}
+
+pub fn first_week_objective(weeks: &[(Week, f64)]) -> f64 {
+    weeks.first().map_or(0.0, |(_, objective)| *objective)
+}
+
+pub fn total_objective(weeks: &[(Week, f64)]) -> f64 {
+    weeks
+        .iter()
+        .fold(0.0, |sum, (_, objective)| saturate(sum + objective))
+}
+
+/// Clamps a cost to the largest finite value, so schedules with huge costs can still be compared
+/// and deposit pheromones.
+pub fn saturate(cost: f64) -> f64 {
+    cost.min(f64::MAX)
+}
+
+impl FromStr for ObjectiveMode {
+    type Err = String;
+
+    /// Accepts `first_week`, `total`, `weighted` with a decay of 0.5 or `weighted:<decay>`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.split_once(':') {
+            None if name == "first_week" => Ok(ObjectiveMode::FirstWeek),
+            None if name == "total" => Ok(ObjectiveMode::TotalHorizon),
+            None if name == "weighted" => Ok(ObjectiveMode::WeightedHorizon { decay: 0.5 }),
+            Some(("weighted", decay)) => decay
+                .parse()
+                .map(|decay| ObjectiveMode::WeightedHorizon { decay })
+                .map_err(|_| format!("invalid decay {} for the weighted objective", decay)),
+            _ => Err(format!(
+                "unknown objective {}, expected first_week, total or weighted[:<decay>]",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ObjectiveMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectiveMode::FirstWeek => write!(f, "first_week"),
+            ObjectiveMode::TotalHorizon => write!(f, "total"),
+            ObjectiveMode::WeightedHorizon { decay } => write!(f, "weighted:{}", decay),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::solver::error::SolverError;
+use crate::solver::solution::Assignment;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Pheromones keyed by the ids of the surgeries on each edge, so they outlive the colony that
+/// learned them and can seed another one. Values are relative to an edge no ant has used yet,
+/// which has a pheromone of 1.
+pub type PheromoneMap = HashMap<(usize, usize), f64>;
+
+/// Pheromones leading ants along an existing schedule: edges between surgeries that follow each
+/// other by day, room and start get `boost` on top of the initial pheromone of 1.
+pub fn from_assignments(assignments: &[Assignment], boost: f64) -> PheromoneMap {
+    let mut pheromones = PheromoneMap::new();
+    add_assignments(&mut pheromones, assignments, boost);
+
+    pheromones
+}
+
+/// Same as `from_assignments`, adding `boost` on top of pheromones already learned for an edge.
+pub fn add_assignments(pheromones: &mut PheromoneMap, assignments: &[Assignment], boost: f64) {
+    let mut ordered = assignments.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|assignment| (assignment.day, assignment.room, assignment.start));
+
+    for edge in ordered.windows(2) {
+        *pheromones
+            .entry((edge[0].surgery_id, edge[1].surgery_id))
+            .or_insert(1.0) += boost;
+    }
+}
+
+/// Writes one `from;to;pheromone` line per edge, sorted by surgery ids.
+pub fn write_csv<W: Write>(pheromones: &PheromoneMap, mut writer: W) -> io::Result<()> {
+    let mut edges = pheromones.iter().collect::<Vec<_>>();
+    edges.sort_by_key(|(edge, _)| **edge);
+
+    writeln!(writer, "from;to;pheromone")?;
+    for ((from, to), pheromone) in edges {
+        writeln!(writer, "{};{};{}", from, to, pheromone)?;
+    }
+
+    Ok(())
+}
+
+pub fn save<P: AsRef<Path>>(pheromones: &PheromoneMap, filename: P) -> Result<(), SolverError> {
+    write_csv(pheromones, BufWriter::new(File::create(filename)?))?;
+
+    Ok(())
+}
+
+pub fn from_csv<P: AsRef<Path>>(filename: P) -> Result<PheromoneMap, SolverError> {
+    from_reader(File::open(filename)?)
+}
+
+/// Reads pheromones written by `write_csv`.
+pub fn from_reader<R: Read>(mut reader: R) -> Result<PheromoneMap, SolverError> {
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+
+    csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .from_reader(content.as_slice())
+        .deserialize()
+        .map(|row| {
+            let (from, to, pheromone): (usize, usize, f64) =
+                row.map_err(|error| SolverError::from_csv_error(error, &content))?;
+            Ok(((from, to), pheromone))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warm_start_adds_to_learned_pheromones() {
+        let assignment = |surgery_id, start| Assignment {
+            surgery_id,
+            room: 1,
+            day: 1,
+            date: None,
+            start,
+        };
+        let mut pheromones = PheromoneMap::from([((1, 2), 5.0), ((3, 1), 0.5)]);
+
+        add_assignments(
+            &mut pheromones,
+            &[assignment(2, 20), assignment(1, 1), assignment(3, 40)],
+            2.0,
+        );
+        assert_eq!(
+            pheromones,
+            PheromoneMap::from([((1, 2), 7.0), ((2, 3), 3.0), ((3, 1), 0.5)])
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use crate::solver::surgery::{DaysWaiting, Priority};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Deadline and penalty of one urgency class.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriorityLevel {
+    pub level: Priority,
+    pub max_days_waiting: DaysWaiting,
+    pub penalty: u32,
+    /// Surgeries of this level are penalized for every day they wait after the first one.
+    #[serde(default)]
+    pub first_day_penalty: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PolicyFile {
+    priority: Vec<PriorityLevel>,
+}
+
+/// Urgency classes of a hospital, by default the 4 levels of the original model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityPolicy {
+    levels: BTreeMap<Priority, PriorityLevel>,
+}
+
+impl Default for PriorityPolicy {
+    fn default() -> Self {
+        Self {
+            levels: [(1, 3, 90), (2, 15, 20), (3, 60, 5), (4, 365, 1)]
+                .iter()
+                .map(|&(level, max_days_waiting, penalty)| {
+                    (
+                        level,
+                        PriorityLevel {
+                            level,
+                            max_days_waiting,
+                            penalty,
+                            first_day_penalty: level == 1,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PriorityPolicy {
+    pub fn from_levels(levels: Vec<PriorityLevel>) -> Result<Self, SolverError> {
+        if levels.is_empty() {
+            return Err(SolverError::Config(
+                "a priority policy needs at least one level".into(),
+            ));
+        }
+
+        let mut policy = Self {
+            levels: BTreeMap::new(),
+        };
+        for level in levels {
+            if policy.levels.contains_key(&level.level) {
+                return Err(SolverError::Config(format!(
+                    "priority level {} is defined more than once",
+                    level.level
+                )));
+            }
+            policy.levels.insert(level.level, level);
+        }
+
+        Ok(policy)
+    }
+
+    /// Reads a `priority` list of levels.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Self, SolverError> {
+        Self::from_levels(config::load::<PolicyFile, _>(filename)?.priority)
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &PriorityLevel> {
+        self.levels.values()
+    }
+
+    pub fn contains(&self, priority: Priority) -> bool {
+        self.levels.contains_key(&priority)
+    }
+
+    pub fn max_days_waiting(&self, priority: Priority) -> DaysWaiting {
+        self.levels[&priority].max_days_waiting
+    }
+
+    pub fn penalty(&self, priority: Priority) -> u32 {
+        self.levels[&priority].penalty
+    }
+
+    pub fn has_first_day_penalty(&self, priority: Priority) -> bool {
+        self.levels
+            .get(&priority)
+            .is_some_and(|level| level.first_day_penalty)
+    }
+}
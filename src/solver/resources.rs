@@ -0,0 +1,176 @@
+use crate::solver::availability::DayAvailability;
+use crate::solver::blocks::Block;
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use crate::solver::rooms::Room;
+use crate::solver::surgeon::SurgeonID;
+use crate::solver::surgery::Speciality;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Room turnover of a speciality that needs more (or less) cleaning than the default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpecialityResources {
+    pub speciality: Speciality,
+    pub cleaning_slots: u8,
+}
+
+/// Limits of a surgeon that doesn't work the default hours, like part-time surgeons.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurgeonResources {
+    pub id: SurgeonID,
+    pub max_day_time: Option<u8>,
+    pub max_week_time: Option<u8>,
+}
+
+/// Time slots available on each room day and how much of them surgeons and cleaning can use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceConfig {
+    pub slots_per_day: u8,
+    pub cleaning_slots: u8,
+    pub surgeon_max_day_time: u8,
+    pub surgeon_max_week_time: u8,
+    #[serde(rename = "speciality")]
+    pub specialities: Vec<SpecialityResources>,
+    #[serde(rename = "surgeon")]
+    pub surgeons: Vec<SurgeonResources>,
+    /// Surgeon calendars, days without an entry use the surgeon limits above.
+    pub availability: Vec<DayAvailability>,
+    /// Room catalog, when empty the solver uses as many interchangeable rooms as requested.
+    #[serde(rename = "room")]
+    pub rooms: Vec<Room>,
+    /// Master surgical schedule, when not empty surgeries can only be placed on its blocks.
+    #[serde(rename = "block")]
+    pub blocks: Vec<Block>,
+}
+
+impl Default for ResourceConfig {
+    fn default() -> Self {
+        Self {
+            slots_per_day: 48,
+            cleaning_slots: 2,
+            surgeon_max_day_time: 26,
+            surgeon_max_week_time: 110,
+            specialities: Vec::new(),
+            surgeons: Vec::new(),
+            availability: Vec::new(),
+            rooms: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+}
+
+impl ResourceConfig {
+    /// Missing fields keep their defaults.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Self, SolverError> {
+        config::load(filename)
+    }
+
+    /// The same resources on a schedule starting `days` days later, dropping calendar entries and
+    /// closed days that are already in the past.
+    pub fn shifted(&self, days: u32) -> Self {
+        let mut resources = self.clone();
+        resources.availability = self
+            .availability
+            .iter()
+            .filter(|availability| availability.day > days)
+            .map(|availability| DayAvailability {
+                day: availability.day - days,
+                ..availability.clone()
+            })
+            .collect();
+        for room in &mut resources.rooms {
+            room.closed_days = room
+                .closed_days
+                .iter()
+                .filter(|&&day| day > days)
+                .map(|day| day - days)
+                .collect();
+        }
+
+        resources
+    }
+
+    /// Last day with a calendar entry or a closed room, 0 without any. Every week starting after it
+    /// offers the same rooms and surgeon days.
+    pub fn last_calendar_day(&self) -> u32 {
+        let closed_days = self.rooms.iter().flat_map(|room| room.closed_days.iter());
+        self.availability
+            .iter()
+            .map(|availability| &availability.day)
+            .chain(closed_days)
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn cleaning_slots(&self, speciality: Speciality) -> u8 {
+        self.specialities
+            .iter()
+            .find(|resources| resources.speciality == speciality)
+            .map_or(self.cleaning_slots, |resources| resources.cleaning_slots)
+    }
+
+    pub fn surgeon_max_day_time(&self, surgeon_id: SurgeonID) -> u8 {
+        self.surgeon(surgeon_id)
+            .and_then(|surgeon| surgeon.max_day_time)
+            .unwrap_or(self.surgeon_max_day_time)
+    }
+
+    pub fn surgeon_max_week_time(&self, surgeon_id: SurgeonID) -> u8 {
+        self.surgeon(surgeon_id)
+            .and_then(|surgeon| surgeon.max_week_time)
+            .unwrap_or(self.surgeon_max_week_time)
+    }
+
+    pub fn surgeon_day(&self, surgeon_id: SurgeonID, day: u32) -> Option<&DayAvailability> {
+        self.availability
+            .iter()
+            .find(|availability| availability.surgeon == surgeon_id && availability.day == day)
+    }
+
+    /// Rooms that can be used on the given day.
+    pub fn rooms_on_day(&self, rooms_count: usize, day: u32) -> Vec<Room> {
+        if self.rooms.is_empty() {
+            (1..=rooms_count).map(Room::new).collect()
+        } else {
+            self.rooms
+                .iter()
+                .filter(|room| room.is_open(day))
+                .cloned()
+                .collect()
+        }
+    }
+
+    pub fn has_blocks(&self) -> bool {
+        !self.blocks.is_empty()
+    }
+
+    /// Blocks of the master schedule on the given day whose room isn't closed by the catalog.
+    pub fn blocks_on_day(&self, day: u32) -> Vec<&Block> {
+        self.blocks
+            .iter()
+            .filter(|block| block.is_on_day(day))
+            .filter(|block| {
+                self.rooms
+                    .iter()
+                    .filter(|room| room.id == block.room)
+                    .all(|room| room.is_open(day))
+            })
+            .collect()
+    }
+
+    /// Whether a block of the speciality falls on one of the first `working_days` of the week.
+    pub fn has_block_for(&self, speciality: Speciality, working_days: u32) -> bool {
+        self.blocks.iter().any(|block| {
+            block.speciality == speciality && (1..=working_days).contains(&block.weekday)
+        })
+    }
+
+    fn surgeon(&self, surgeon_id: SurgeonID) -> Option<&SurgeonResources> {
+        self.surgeons
+            .iter()
+            .find(|surgeon| surgeon.id == surgeon_id)
+    }
+}
@@ -0,0 +1,48 @@
+use crate::solver::config;
+use crate::solver::error::SolverError;
+use crate::solver::surgery::Speciality;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub type RoomID = usize;
+
+/// An operating room of the hospital and what it can be used for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Room {
+    pub id: RoomID,
+    /// Specialities supported by the room, any speciality when empty.
+    #[serde(default)]
+    pub specialities: Vec<Speciality>,
+    /// Days, numbered from 1, when the room can't be used.
+    #[serde(default)]
+    pub closed_days: Vec<u32>,
+}
+
+impl Room {
+    /// A room without restrictions, like the ones created when there is no catalog.
+    pub fn new(id: RoomID) -> Self {
+        Self {
+            id,
+            specialities: Vec::new(),
+            closed_days: Vec::new(),
+        }
+    }
+
+    pub fn allows(&self, speciality: Speciality) -> bool {
+        self.specialities.is_empty() || self.specialities.contains(&speciality)
+    }
+
+    pub fn is_open(&self, day: u32) -> bool {
+        !self.closed_days.contains(&day)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RoomsFile {
+    room: Vec<Room>,
+}
+
+/// Reads the catalog from a `room` list.
+pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Vec<Room>, SolverError> {
+    Ok(config::load::<RoomsFile, _>(filename)?.room)
+}
@@ -0,0 +1,244 @@
+use crate::solver::config::SolverConfig;
+use crate::solver::error::SolverError;
+use crate::solver::instance::Instance;
+use crate::solver::surgeon::SurgeonID;
+use crate::solver::surgery::{DaysWaiting, Priority, Speciality, Surgery};
+use crate::solver::Solver;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// A referral that joins the waiting list right before the given week is planned.
+#[derive(Debug, Clone)]
+pub struct Arrival {
+    /// Week numbered from 1, the first planned week.
+    pub week: usize,
+    pub surgery: Surgery,
+}
+
+/// What happened on a simulated week.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeekReport {
+    /// Week numbered from 1.
+    pub week: usize,
+    pub arrivals: usize,
+    pub performed: usize,
+    /// Surgeries still waiting at the end of the week.
+    pub backlog: usize,
+    /// Surgeries performed after their deadline plus the ones still waiting past it at the end of
+    /// the week, by priority.
+    pub deadline_violations: BTreeMap<Priority, usize>,
+    /// Share of the room slots of the week used by surgeries and cleaning.
+    pub utilisation: f64,
+    /// Objective function of the performed week.
+    pub objective: f64,
+}
+
+pub fn arrivals_from_csv<P: AsRef<Path>>(filename: P) -> Result<Vec<Arrival>, SolverError> {
+    arrivals_from_reader(File::open(filename)?)
+}
+
+/// Reads arrivals on the instance csv format with the arrival week as the first column.
+pub fn arrivals_from_reader<R: Read>(mut reader: R) -> Result<Vec<Arrival>, SolverError> {
+    // Semana (k),Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)
+    type Record = (
+        usize,
+        usize,
+        Priority,
+        DaysWaiting,
+        Speciality,
+        SurgeonID,
+        u8,
+    );
+
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+
+    csv::Reader::from_reader(content.as_slice())
+        .deserialize()
+        .map(|row| {
+            let record: Record =
+                row.map_err(|error| SolverError::from_csv_error(error, &content))?;
+            let (week, id, priority, days_waiting, speciality, surgeon_id, duration) = record;
+
+            Ok(Arrival {
+                week,
+                surgery: Surgery::new(id, duration, days_waiting, priority, speciality, surgeon_id),
+            })
+        })
+        .collect()
+}
+
+/// Re-plans the waiting list every week: solves it, performs the first week of the best schedule
+/// and ages whatever is left by a week before the next arrivals join it.
+pub fn simulate(
+    instance: &Instance,
+    arrivals: &[Arrival],
+    weeks: usize,
+    config: &SolverConfig,
+) -> Result<Vec<WeekReport>, SolverError> {
+    let mut waiting_list = instance.surgeries().iter().cloned().collect::<Vec<_>>();
+    let mut reports = Vec::with_capacity(weeks);
+
+    for week in 1..=weeks {
+        let week_arrivals = arrivals
+            .iter()
+            .filter(|arrival| arrival.week.max(1) == week)
+            .map(|arrival| arrival.surgery.clone())
+            .collect::<Vec<_>>();
+        let arrivals_count = week_arrivals.len();
+        waiting_list.extend(week_arrivals);
+
+        let mut week_config = config.clone();
+        week_config.resources = config.resources.shifted(7 * (week as u32 - 1));
+        week_config.horizon = config.horizon.shifted(week - 1);
+        week_config.seed = config.seed.map(|seed| seed.wrapping_add(week as u64 - 1));
+
+        let mut report = WeekReport {
+            week,
+            arrivals: arrivals_count,
+            performed: 0,
+            backlog: 0,
+            deadline_violations: BTreeMap::new(),
+            utilisation: 0.0,
+            objective: 0.0,
+        };
+
+        let mut performed = HashSet::new();
+        if !waiting_list.is_empty() {
+            let solution = Solver::solve(
+                &Instance::from_surgeries(waiting_list.clone()),
+                &week_config,
+            )?;
+            // Without any round (e.g. `max_rounds_count(0)`) there is no schedule, so nothing is
+            // performed and the whole waiting list is carried over
+            if let Some((first_week, objective)) = solution.weeks.first() {
+                report.objective = *objective;
+
+                let mut used_slots = 0;
+                for (day_index, day) in first_week.days().iter().enumerate() {
+                    for room in day.rooms() {
+                        used_slots += u32::from(room.used_slots());
+                        for surgery in room.surgeries() {
+                            if surgery.is_overdue_on(
+                                &config.priority_policy,
+                                first_week.day_number(day_index),
+                            ) {
+                                *report
+                                    .deadline_violations
+                                    .entry(surgery.priority)
+                                    .or_default() += 1;
+                            }
+                            performed.insert(surgery.id);
+                        }
+                    }
+                }
+                report.utilisation = f64::from(used_slots) / f64::from(week_capacity(&week_config));
+            }
+        }
+
+        waiting_list.retain(|surgery| !performed.contains(&surgery.id));
+        for surgery in &waiting_list {
+            if surgery.is_overdue_on(&config.priority_policy, 7) {
+                *report
+                    .deadline_violations
+                    .entry(surgery.priority)
+                    .or_default() += 1;
+            }
+        }
+        for surgery in &mut waiting_list {
+            surgery.days_waiting = surgery.days_waiting.saturating_add(7);
+        }
+
+        report.performed = performed.len();
+        report.backlog = waiting_list.len();
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+/// Room slots available on the first week of the configured horizon.
+fn week_capacity(config: &SolverConfig) -> u32 {
+    let resources = &config.resources;
+    let rooms_count = config
+        .horizon
+        .working_days_of_week(0)
+        .into_iter()
+        .map(|day| {
+            if resources.has_blocks() {
+                resources.blocks_on_day(day).len()
+            } else {
+                resources.rooms_on_day(config.rooms_count, day).len()
+            }
+        })
+        .sum::<usize>();
+
+    (rooms_count as u32 * u32::from(resources.slots_per_day)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::resources::ResourceConfig;
+
+    // Surgeon 1 can only operate once a week, so one of its priority 3 surgeries (60 days) waits
+    // for the second week, already overdue when the first week ends
+    fn instance() -> Instance {
+        Instance::from_surgeries(vec![
+            Surgery::new(1, 20, 54, 3, 1, 1),
+            Surgery::new(2, 20, 54, 3, 1, 1),
+        ])
+    }
+
+    fn config() -> SolverConfig {
+        SolverConfig::new()
+            .threads_count(1)
+            .ants_count(2)
+            .max_rounds_count(5)
+            .seed(7)
+            .resources(ResourceConfig {
+                surgeon_max_week_time: 26,
+                ..ResourceConfig::default()
+            })
+    }
+
+    #[test]
+    fn waiting_surgeries_age_and_arrivals_join_on_their_week() {
+        let content = "Semana (k),Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)\n\
+            2,3,4,0,1,2,10\n\
+            3,4,4,0,1,2,10\n";
+        let arrivals = arrivals_from_reader(content.as_bytes()).unwrap();
+
+        let reports = simulate(&instance(), &arrivals, 3, &config()).unwrap();
+
+        let summary = reports
+            .iter()
+            .map(|report| {
+                (
+                    report.week,
+                    report.arrivals,
+                    report.performed,
+                    report.backlog,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![(1, 0, 1, 1), (2, 1, 2, 0), (3, 1, 1, 0)]);
+        // Week 1 counts the surgery left waiting past its deadline. On week 2 it is performed on
+        // the first day after waiting 54 + 7 days, overdue only because it aged a week
+        assert_eq!(reports[0].deadline_violations, BTreeMap::from([(3, 1)]));
+        assert_eq!(reports[1].deadline_violations, BTreeMap::from([(3, 1)]));
+        assert!(reports[2].deadline_violations.is_empty());
+    }
+
+    #[test]
+    fn a_week_without_schedule_keeps_the_whole_waiting_list() {
+        let reports = simulate(&instance(), &[], 1, &config().max_rounds_count(0)).unwrap();
+
+        assert_eq!(reports[0].performed, 0);
+        assert_eq!(reports[0].backlog, 2);
+        assert_eq!(reports[0].deadline_violations, BTreeMap::from([(3, 2)]));
+        assert_eq!(reports[0].objective, 0.0);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::solver::checkpoint::Checkpoint;
+use crate::solver::error::{line_at, SolverError};
+use crate::solver::objective;
+use crate::solver::pheromones::PheromoneMap;
+use crate::solver::week::Week;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Where and when a single surgery was scheduled, as written on solution files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub surgery_id: usize,
+    pub room: usize,
+    /// Number of the day on the whole schedule, see `Horizon`.
+    pub day: u32,
+    pub date: Option<NaiveDate>,
+    pub start: u8,
+}
+
+/// Stopping criterion that ended the rounds of an execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StopReason {
+    /// Every round allowed by `max_rounds_count` was run.
+    MaxRounds,
+    /// `max_rounds_without_improvement` rounds ran without a better schedule.
+    NoImprovement,
+    /// A schedule reached the target objective.
+    Target,
+    /// The time limit passed, counting the time before the checkpoint of a resumed execution.
+    TimeLimit,
+    /// The stop flag was set.
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StopReason::MaxRounds => "max_rounds",
+            StopReason::NoImprovement => "max_rounds_improv",
+            StopReason::Target => "target",
+            StopReason::TimeLimit => "time_limit",
+            StopReason::Interrupted => "interrupted",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Best schedule found by a solver execution.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// Value optimised by the colony, according to the objective mode.
+    pub objective: f64,
+    pub first_week_objective: f64,
+    pub total_objective: f64,
+    pub best_round: u32,
+    pub weeks: Vec<(Week, f64)>,
+    pub elapsed_time: Duration,
+    pub assignments: Vec<Assignment>,
+    /// Pheromones of the colony when it stopped, keyed by surgery ids.
+    pub pheromones: PheromoneMap,
+    pub stop_reason: StopReason,
+}
+
+impl Solution {
+    pub fn new(
+        objective: f64,
+        best_round: u32,
+        weeks: Vec<(Week, f64)>,
+        elapsed_time: Duration,
+        pheromones: PheromoneMap,
+        stop_reason: StopReason,
+    ) -> Self {
+        let assignments = Self::collect_assignments(&weeks);
+        Self {
+            objective,
+            first_week_objective: objective::first_week_objective(&weeks),
+            total_objective: objective::total_objective(&weeks),
+            best_round,
+            weeks,
+            elapsed_time,
+            assignments,
+            pheromones,
+            stop_reason,
+        }
+    }
+
+    /// Best solution of a resumed execution that found nothing better than its checkpoint. Only
+    /// the assignments were saved, so `weeks` is empty.
+    pub fn from_checkpoint(
+        checkpoint: &Checkpoint,
+        elapsed_time: Duration,
+        pheromones: PheromoneMap,
+        stop_reason: StopReason,
+    ) -> Self {
+        Self {
+            objective: checkpoint.best_objective,
+            first_week_objective: checkpoint.best_first_week_objective,
+            total_objective: checkpoint.best_total_objective,
+            best_round: checkpoint.best_round,
+            weeks: Vec::new(),
+            elapsed_time,
+            assignments: checkpoint.best_assignments.clone(),
+            pheromones,
+            stop_reason,
+        }
+    }
+
+    pub(crate) fn collect_assignments(weeks: &[(Week, f64)]) -> Vec<Assignment> {
+        let mut assignments = Vec::new();
+
+        for (week, _) in weeks {
+            for (day_index, day) in week.days().iter().enumerate() {
+                for room in day.rooms() {
+                    for (surgery, (schedule, _)) in
+                        room.surgeries().iter().zip(room.scheduled_surgeons())
+                    {
+                        assignments.push(Assignment {
+                            surgery_id: surgery.id,
+                            room: room.id(),
+                            day: week.day_number(day_index),
+                            date: week.date(day_index),
+                            start: schedule.start,
+                        });
+                    }
+                }
+            }
+        }
+
+        assignments.sort_by_key(|assignment| assignment.surgery_id);
+
+        assignments
+    }
+
+    /// Writes the assignments with the same columns of the `sample_data` solutions, plus the
+    /// calendar date of each day when the horizon has a start date.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let has_dates = self
+            .assignments
+            .iter()
+            .any(|assignment| assignment.date.is_some());
+
+        write!(writer, "Cirurgia (c);Sala (r);Dia (d);Horário (t)")?;
+        if has_dates {
+            write!(writer, ";Data")?;
+        }
+        writeln!(writer)?;
+        for assignment in &self.assignments {
+            write!(
+                writer,
+                "{};{};{};{}",
+                assignment.surgery_id, assignment.room, assignment.day, assignment.start
+            )?;
+            if let Some(date) = assignment.date {
+                write!(writer, ";{}", date)?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn assignments_from_csv<P: AsRef<Path>>(filename: P) -> Result<Vec<Assignment>, SolverError> {
+    assignments_from_reader(File::open(filename)?)
+}
+
+/// Reads a solution csv, either comma separated like the `sample_data` solutions or semicolon
+/// separated like the ones written by `Solution::write_csv`, with or without the date column.
+pub fn assignments_from_reader<R: Read>(mut reader: R) -> Result<Vec<Assignment>, SolverError> {
+    // Cirurgia (c);Sala (r);Dia (d);Horário (t)
+    type Record = (usize, usize, u32, u8);
+
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+    let header = content
+        .split(|&byte| byte == b'\n')
+        .next()
+        .unwrap_or_default();
+    let delimiter = if header.contains(&b';') { b';' } else { b',' };
+
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_slice())
+        .records()
+        .map(|row| {
+            let record = row.map_err(|error| SolverError::from_csv_error(error, &content))?;
+            let line = record
+                .position()
+                .map_or(0, |position| line_at(&content, position.byte()));
+            let at_line = |error: SolverError| match error {
+                SolverError::Parse {
+                    column, message, ..
+                } => SolverError::Parse {
+                    line,
+                    column,
+                    message,
+                },
+                error => error,
+            };
+
+            let fields = record.iter().take(4).collect::<csv::StringRecord>();
+            let (surgery_id, room, day, start): Record = fields
+                .deserialize(None)
+                .map_err(|error| at_line(error.into()))?;
+            let date = match record.get(4).filter(|date| !date.is_empty()) {
+                Some(date) => Some(date.parse().map_err(|_| SolverError::Parse {
+                    line,
+                    column: Some(5),
+                    message: format!("invalid date {}", date),
+                })?),
+                None => None,
+            };
+
+            Ok(Assignment {
+                surgery_id,
+                room,
+                day,
+                date,
+                start,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::horizon::Horizon;
+    use crate::solver::policy::PriorityPolicy;
+    use crate::solver::resources::ResourceConfig;
+    use crate::solver::surgery::Surgery;
+    use crate::solver::week::placement::PlacementStrategy;
+    use std::sync::Arc;
+
+    #[test]
+    fn objective_and_solution_file_use_the_same_day() {
+        // Monday of the second week is a holiday, so its first working day is day 9
+        let start_date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let horizon = Horizon {
+            start_date: Some(start_date),
+            holidays: vec![NaiveDate::from_ymd_opt(2024, 1, 8).unwrap()],
+            ..Horizon::default()
+        };
+        let mut week = Week::new(
+            1,
+            1,
+            Arc::new(vec![1]),
+            Arc::new(ResourceConfig::default()),
+            PlacementStrategy::Append,
+            Arc::new(horizon),
+        );
+        let surgery = Surgery::new(1, 10, 2, 1, 1, 1);
+        week.schedule_surgery(surgery.clone()).unwrap();
+        let policy = PriorityPolicy::default();
+        let objective = week.calculate_objective_function(&[], Arc::new(policy.clone()));
+        let solution = Solution::new(
+            objective,
+            1,
+            vec![(week, objective)],
+            Duration::default(),
+            PheromoneMap::new(),
+            StopReason::MaxRounds,
+        );
+
+        let mut csv = Vec::new();
+        solution.write_csv(&mut csv).unwrap();
+        let assignments = assignments_from_reader(csv.as_slice()).unwrap();
+        let day = assignments[0].day;
+        assert_eq!(day, 9);
+        assert_eq!(assignments[0].date, NaiveDate::from_ymd_opt(2024, 1, 9));
+        assert_eq!(
+            objective,
+            surgery.scheduled_objective_function(&policy, day)
+                + surgery.penalty_for_not_scheduling_on_first_day(&policy, day)
+        );
+    }
+}
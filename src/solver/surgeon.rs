@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
+use super::error::SolverError;
+use super::resources::ResourceConfig;
 use super::surgery::Surgery;
+use std::convert::TryFrom;
 use std::ops::Range;
 
 pub type SurgeonID = usize;
@@ -12,23 +15,30 @@ pub struct SurgeonWeekly {
 }
 
 impl SurgeonWeekly {
-    pub fn new() -> Self {
+    pub fn new(max_week_time: u8) -> Self {
         Self {
-            max_week_time: 110,
+            max_week_time,
             current_week_time: 0,
         }
     }
 
     pub fn has_availability(&self, surgery: &Surgery) -> bool {
-        self.current_week_time + surgery.duration <= self.max_week_time
+        // Wider arithmetic, as configured limits may be close to u8::MAX
+        u16::from(self.current_week_time) + u16::from(surgery.duration)
+            <= u16::from(self.max_week_time)
     }
 
-    pub fn allocate(&mut self, surgery: &Surgery) {
-        if self.current_week_time + surgery.duration > self.max_week_time {
-            panic!("Tried to allocate a surgery that surpasses surgeon max weekly time!");
+    pub fn allocate(&mut self, surgery: &Surgery) -> Result<(), SolverError> {
+        if !self.has_availability(surgery) {
+            return Err(SolverError::CapacityExceeded {
+                surgery_id: surgery.id,
+                reason: "surpasses surgeon max weekly time",
+            });
         }
 
         self.current_week_time += surgery.duration;
+
+        Ok(())
     }
 
     pub fn deallocate(&mut self, surgery: &Surgery) {
@@ -39,8 +49,13 @@ impl SurgeonWeekly {
         self.current_week_time -= surgery.duration;
     }
 
-    pub fn many_from_ids(ids: &[SurgeonID]) -> HashMap<SurgeonID, SurgeonWeekly> {
-        ids.iter().map(|id| (*id, Self::new())).collect()
+    pub fn many_from_ids(
+        ids: &[SurgeonID],
+        resources: &ResourceConfig,
+    ) -> HashMap<SurgeonID, SurgeonWeekly> {
+        ids.iter()
+            .map(|id| (*id, Self::new(resources.surgeon_max_week_time(*id))))
+            .collect()
     }
 }
 
@@ -49,54 +64,134 @@ pub struct SurgeonDaily {
     max_day_time: u8,
     current_day_time: u8,
     scheduled_times: Vec<(Range<u8>, Surgery)>,
+    // None when the surgeon can operate on any time of the day
+    windows: Option<Vec<Range<u8>>>,
 }
 
 impl SurgeonDaily {
-    pub fn new() -> Self {
+    pub fn new(max_day_time: u8) -> Self {
         Self {
-            max_day_time: 26,
+            max_day_time,
             current_day_time: 0,
             scheduled_times: vec![],
+            windows: None,
         }
     }
 
+    pub fn with_windows(mut self, windows: Vec<Range<u8>>) -> Self {
+        self.windows = Some(windows);
+        self
+    }
+
     pub fn has_availability(&self, surgery: &Surgery) -> bool {
-        self.current_day_time + surgery.duration <= self.max_day_time
+        u16::from(self.current_day_time) + u16::from(surgery.duration)
+            <= u16::from(self.max_day_time)
+            && self
+                .windows
+                .as_ref()
+                .is_none_or(|windows| !windows.is_empty())
+    }
+
+    pub fn max_day_time(&self) -> u8 {
+        self.max_day_time
+    }
+
+    pub fn scheduled_times(&self) -> impl Iterator<Item = &Range<u8>> {
+        self.scheduled_times.iter().map(|scheduled| &scheduled.0)
+    }
+
+    /// Availability windows of the day, empty when the surgeon can operate at any time.
+    pub fn windows(&self) -> &[Range<u8>] {
+        self.windows.as_deref().unwrap_or(&[])
     }
 
     pub fn can_be_allocated(&self, schedule_time: &Range<u8>) -> bool {
-        self.scheduled_times.iter().all(|scheduled| {
-            scheduled.0.end < schedule_time.start || scheduled.0.start > schedule_time.end
+        self.is_inside_windows(schedule_time)
+            && self.scheduled_times.iter().all(|scheduled| {
+                scheduled.0.end < schedule_time.start || scheduled.0.start > schedule_time.end
+            })
+    }
+
+    fn is_inside_windows(&self, schedule_time: &Range<u8>) -> bool {
+        self.windows.as_ref().is_none_or(|windows| {
+            windows.iter().any(|window| {
+                window.start <= schedule_time.start && schedule_time.end <= window.end
+            })
         })
     }
 
+    /// When `allocate_next` would schedule the surgery: right after the surgeon's latest surgery,
+    /// or at the start of the first availability window after it.
+    pub fn next_schedule_time(&self, surgery: &Surgery, cleaning_slots: u8) -> Option<Range<u8>> {
+        // Surgeries placed on gaps may come after the latest one on the list
+        let start = self
+            .scheduled_times()
+            .map(|time| time.end)
+            .max()
+            .unwrap_or(1);
+        let length = u16::from(surgery.duration) + u16::from(cleaning_slots);
+
+        // Wider arithmetic, slots that don't fit a u8 are past the end of any day
+        let time_from = |start: u8| {
+            let end = u8::try_from(u16::from(start) + length).ok()?;
+            Some(start..end)
+        };
+        match &self.windows {
+            None => time_from(start),
+            Some(windows) => windows
+                .iter()
+                .filter_map(|window| {
+                    time_from(start.max(window.start)).filter(|time| time.end <= window.end)
+                })
+                .next(),
+        }
+    }
+
     pub fn last_scheduled_time(&self) -> Range<u8> {
         self.scheduled_times.last().unwrap().0.clone()
     }
 
-    pub fn allocate_next(&mut self, surgery: Surgery) {
-        if self.current_day_time + surgery.duration > self.max_day_time {
-            panic!("Tried to allocate a surgery that surpasses surgeon max daily time!");
-        }
+    pub fn allocate_next(
+        &mut self,
+        surgery: Surgery,
+        cleaning_slots: u8,
+    ) -> Result<(), SolverError> {
+        self.check_daily_time(&surgery)?;
+        let schedule_time = self.next_schedule_time(&surgery, cleaning_slots).ok_or(
+            SolverError::CapacityExceeded {
+                surgery_id: surgery.id,
+                reason: "is outside surgeon availability",
+            },
+        )?;
 
         self.current_day_time += surgery.duration;
-        if self.scheduled_times.is_empty() {
-            self.scheduled_times
-                .push((1..(surgery.duration + 2), surgery));
-        } else {
-            let last_time = self.scheduled_times.last().unwrap().0.end;
-            self.scheduled_times
-                .push((last_time..(last_time + 2 + surgery.duration), surgery));
-        }
+        self.scheduled_times.push((schedule_time, surgery));
+
+        Ok(())
     }
 
-    pub fn allocate_by_schedule(&mut self, schedule_time: Range<u8>, surgery: Surgery) {
-        if self.current_day_time + surgery.duration > self.max_day_time {
-            panic!("Tried to allocate a surgery that surpasses surgeon max daily time!");
-        }
+    pub fn allocate_by_schedule(
+        &mut self,
+        schedule_time: Range<u8>,
+        surgery: Surgery,
+    ) -> Result<(), SolverError> {
+        self.check_daily_time(&surgery)?;
         self.current_day_time += surgery.duration;
 
         self.scheduled_times.push((schedule_time, surgery));
+
+        Ok(())
+    }
+
+    fn check_daily_time(&self, surgery: &Surgery) -> Result<(), SolverError> {
+        if !self.has_availability(surgery) {
+            Err(SolverError::CapacityExceeded {
+                surgery_id: surgery.id,
+                reason: "surpasses surgeon max daily time",
+            })
+        } else {
+            Ok(())
+        }
     }
 
     pub fn deallocate(&mut self, surgery: &Surgery) {
@@ -116,7 +211,28 @@ impl SurgeonDaily {
         self.scheduled_times.remove(to_remove_index);
     }
 
-    pub fn many_from_ids(ids: &[SurgeonID]) -> HashMap<SurgeonID, SurgeonDaily> {
-        ids.iter().map(|id| (*id, Self::new())).collect()
+    pub fn many_from_ids(
+        ids: &[SurgeonID],
+        resources: &ResourceConfig,
+        day: u32,
+    ) -> HashMap<SurgeonID, SurgeonDaily> {
+        ids.iter()
+            .map(|id| (*id, Self::for_day(*id, resources, day)))
+            .collect()
+    }
+
+    /// A surgeon with the limits and windows from its calendar on the given day.
+    pub fn for_day(id: SurgeonID, resources: &ResourceConfig, day: u32) -> Self {
+        let mut surgeon = Self::new(resources.surgeon_max_day_time(id));
+        if let Some(availability) = resources.surgeon_day(id, day) {
+            if let Some(max_day_time) = availability.max_day_time {
+                surgeon.max_day_time = max_day_time;
+            }
+            if let Some(windows) = availability.windows() {
+                surgeon = surgeon.with_windows(windows);
+            }
+        }
+
+        surgeon
     }
 }
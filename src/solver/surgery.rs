@@ -4,9 +4,9 @@ pub type Speciality = u32;
 pub type Priority = usize;
 pub type DaysWaiting = u32;
 
+use super::objective::saturate;
+use super::policy::PriorityPolicy;
 use super::surgeon::SurgeonID;
-use std::collections::HashMap;
-use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct Surgery {
@@ -51,42 +51,172 @@ impl Surgery {
         }
     }
 
-    pub fn scheduled_objective_function(
-        &self,
-        max_days_waiting: Arc<HashMap<Priority, DaysWaiting>>,
-        day: u32,
-    ) -> f64 {
-        let days_waited = self.days_waiting + 2 + day;
-        let my_max_days_waiting = max_days_waiting[&self.priority];
+    /// Whether the surgery has passed its deadline when performed on the given day, the same way
+    /// the objective function counts it. Day 7 means still waiting at the end of the week.
+    pub fn is_overdue_on(&self, priority_policy: &PriorityPolicy, day: u32) -> bool {
+        u64::from(self.days_waiting) + 2 + u64::from(day)
+            > u64::from(priority_policy.max_days_waiting(self.priority)) + 1
+    }
+
+    // Every cost is computed on f64, as waiting times and day numbers of long horizons easily
+    // overflow the integer types, and saturated so it never becomes infinite
+    fn days_waited(&self, day: u32) -> f64 {
+        f64::from(self.days_waiting) + 2.0 + f64::from(day)
+    }
+
+    pub fn scheduled_objective_function(&self, priority_policy: &PriorityPolicy, day: u32) -> f64 {
+        saturate(self.waiting_cost(day) + self.overdue_cost(priority_policy, day))
+    }
+
+    /// Part of the scheduled objective due to the days waited until the surgery is performed.
+    pub fn waiting_cost(&self, day: u32) -> f64 {
+        saturate(self.days_waited(day).powi(2))
+    }
+
+    /// Part of the scheduled objective due to the days waited past the deadline of the priority.
+    pub fn overdue_cost(&self, priority_policy: &PriorityPolicy, day: u32) -> f64 {
+        let days_waited = self.days_waited(day);
+        let my_max_days_waiting = f64::from(priority_policy.max_days_waiting(self.priority));
 
-        if my_max_days_waiting + 1 >= days_waited {
-            days_waited.pow(2).into()
+        if my_max_days_waiting + 1.0 >= days_waited {
+            0.0
+        } else {
+            saturate((days_waited - my_max_days_waiting).powi(2))
+        }
+    }
+
+    pub fn not_scheduled_objective_function(&self, priority_policy: &PriorityPolicy) -> f64 {
+        let days_waiting = f64::from(self.days_waiting);
+        let my_max_days_waiting = f64::from(priority_policy.max_days_waiting(self.priority));
+        if my_max_days_waiting + 1.0 >= days_waiting + 9.0 {
+            saturate((days_waiting + 7.0).powi(2))
         } else {
-            (days_waited.pow(2) + (days_waited - my_max_days_waiting).pow(2)).into()
+            saturate(
+                ((days_waiting + 7.0).powi(2) + (days_waiting + 9.0 - my_max_days_waiting).powi(2))
+                    * f64::from(priority_policy.penalty(self.priority)),
+            )
         }
     }
 
-    pub fn not_scheduled_objective_function(
+    pub fn penalty_for_not_scheduling_on_first_day(
         &self,
-        max_days_waiting: Arc<HashMap<Priority, DaysWaiting>>,
-        priority_penalties: Arc<HashMap<Priority, u32>>,
+        priority_policy: &PriorityPolicy,
+        day: u32,
     ) -> f64 {
-        let my_max_days_waiting = max_days_waiting[&self.priority];
-        if my_max_days_waiting + 1 >= self.days_waiting + 9 {
-            (self.days_waiting + 7).pow(2).into()
+        if priority_policy.has_first_day_penalty(self.priority) {
+            let exponent = day.min(i32::MAX as u32) as i32;
+            saturate((10.0 * (f64::from(self.days_waiting) + 2.0)).powi(exponent))
         } else {
-            (((self.days_waiting + 7).pow(2)
-                + (self.days_waiting + 9 - my_max_days_waiting).pow(2))
-                * priority_penalties[&self.priority])
-                .into()
+            0.0
         }
     }
+}
 
-    pub fn penalty_for_not_scheduling_on_first_day(&self, day: u32) -> f64 {
-        if self.priority == 1 {
-            (10 * (self.days_waiting + 2)).pow(day).into()
-        } else {
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTREME_DAYS_WAITING: [DaysWaiting; 7] = [0, 1, 364, 365, 10_000, 1_000_000, u32::MAX];
+    const EXTREME_DAYS: [u32; 6] = [1, 2, 7, 12, 365, u32::MAX];
+
+    fn surgery(priority: Priority, days_waiting: DaysWaiting) -> Surgery {
+        Surgery::new(1, 10, days_waiting, priority, 1, 1)
+    }
+
+    fn assert_valid_cost(cost: f64) {
+        assert!(cost.is_finite(), "cost {} is not finite", cost);
+        assert!(cost >= 0.0, "cost {} is negative", cost);
+    }
+
+    #[test]
+    fn costs_match_the_integer_formulas_on_small_values() {
+        let policy = PriorityPolicy::default();
+
+        // 2 + 2 + 1 days waited, 2 past the 3 days deadline of priority 1
+        let urgent = surgery(1, 2);
+        assert_eq!(urgent.scheduled_objective_function(&policy, 1), 25.0 + 4.0);
+        assert_eq!(
+            urgent.penalty_for_not_scheduling_on_first_day(&policy, 2),
+            1600.0
+        );
+        assert_eq!(
+            urgent.not_scheduled_objective_function(&policy),
+            (81.0 + 64.0) * 90.0
+        );
+
+        let elective = surgery(4, 10);
+        assert_eq!(elective.scheduled_objective_function(&policy, 3), 225.0);
+        assert_eq!(
+            elective.penalty_for_not_scheduling_on_first_day(&policy, 3),
             0.0
+        );
+        assert_eq!(elective.not_scheduled_objective_function(&policy), 289.0);
+    }
+
+    #[test]
+    fn costs_stay_finite_on_extreme_waiting_times() {
+        let policy = PriorityPolicy::default();
+
+        for priority in 1..=4 {
+            for &days_waiting in &EXTREME_DAYS_WAITING {
+                let surgery = surgery(priority, days_waiting);
+                assert_valid_cost(surgery.not_scheduled_objective_function(&policy));
+                for &day in &EXTREME_DAYS {
+                    assert_valid_cost(surgery.scheduled_objective_function(&policy, day));
+                    assert_valid_cost(
+                        surgery.penalty_for_not_scheduling_on_first_day(&policy, day),
+                    );
+                }
+            }
         }
     }
+
+    #[test]
+    fn costs_never_decrease_with_waiting_time() {
+        let policy = PriorityPolicy::default();
+
+        for priority in 1..=4 {
+            for pair in EXTREME_DAYS_WAITING.windows(2) {
+                let (less, more) = (surgery(priority, pair[0]), surgery(priority, pair[1]));
+                assert!(
+                    less.not_scheduled_objective_function(&policy)
+                        <= more.not_scheduled_objective_function(&policy)
+                );
+                for &day in &EXTREME_DAYS {
+                    assert!(
+                        less.scheduled_objective_function(&policy, day)
+                            <= more.scheduled_objective_function(&policy, day)
+                    );
+                    assert!(
+                        less.penalty_for_not_scheduling_on_first_day(&policy, day)
+                            <= more.penalty_for_not_scheduling_on_first_day(&policy, day)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_day_penalty_saturates_instead_of_wrapping() {
+        let policy = PriorityPolicy::default();
+        let urgent = surgery(1, 365);
+
+        // (10 * 367)^12 overflows u32 and u64, but is still an exact power on f64
+        assert_eq!(
+            urgent.penalty_for_not_scheduling_on_first_day(&policy, 12),
+            3670f64.powi(12)
+        );
+        assert_eq!(
+            urgent.penalty_for_not_scheduling_on_first_day(&policy, 365),
+            f64::MAX
+        );
+    }
+
+    #[test]
+    fn overdue_check_does_not_overflow() {
+        let policy = PriorityPolicy::default();
+
+        assert!(surgery(4, u32::MAX).is_overdue_on(&policy, u32::MAX));
+        assert!(!surgery(4, 0).is_overdue_on(&policy, 7));
+    }
 }
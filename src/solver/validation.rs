@@ -0,0 +1,435 @@
+use crate::solver::config::SolverConfig;
+use crate::solver::error::{line_at, SolverError};
+use crate::solver::surgeon::{SurgeonDaily, SurgeonID, SurgeonWeekly};
+use crate::solver::surgery::{DaysWaiting, Priority, Speciality, Surgery};
+use crate::solver::week::room_per_day::RoomPerDay;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Something that makes an instance unsolvable or that would be silently lost when loading it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    Malformed(String),
+    UnknownPriority {
+        surgery_id: usize,
+        priority: Priority,
+    },
+    LongerThanRoomDay {
+        surgery_id: usize,
+        duration: u8,
+    },
+    LongerThanSurgeonDay {
+        surgery_id: usize,
+        surgeon_id: SurgeonID,
+        duration: u8,
+    },
+    LongerThanSurgeonWeek {
+        surgery_id: usize,
+        surgeon_id: SurgeonID,
+        duration: u8,
+    },
+    NoRoomForSpeciality {
+        surgery_id: usize,
+        speciality: Speciality,
+    },
+    NoBlockForSpeciality {
+        surgery_id: usize,
+        speciality: Speciality,
+    },
+    DuplicateId {
+        surgery_id: usize,
+        first_line: Option<u64>,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Malformed(message) => write!(f, "malformed line: {}", message),
+            Problem::UnknownPriority {
+                surgery_id,
+                priority,
+            } => write!(
+                f,
+                "surgery {} has priority {} which is not on the priority policy",
+                surgery_id, priority
+            ),
+            Problem::LongerThanRoomDay {
+                surgery_id,
+                duration,
+            } => write!(
+                f,
+                "surgery {} lasts {} slots and never fits a room day with its cleaning time",
+                surgery_id, duration
+            ),
+            Problem::LongerThanSurgeonDay {
+                surgery_id,
+                surgeon_id,
+                duration,
+            } => write!(
+                f,
+                "surgery {} lasts {} slots, more than surgeon {} can work in a day",
+                surgery_id, duration, surgeon_id
+            ),
+            Problem::LongerThanSurgeonWeek {
+                surgery_id,
+                surgeon_id,
+                duration,
+            } => write!(
+                f,
+                "surgery {} lasts {} slots, more than surgeon {} can work in a week",
+                surgery_id, duration, surgeon_id
+            ),
+            Problem::NoRoomForSpeciality {
+                surgery_id,
+                speciality,
+            } => write!(
+                f,
+                "surgery {} has speciality {} which no room of the catalog supports",
+                surgery_id, speciality
+            ),
+            Problem::NoBlockForSpeciality {
+                surgery_id,
+                speciality,
+            } => write!(
+                f,
+                "surgery {} has speciality {} which has no block on the master schedule",
+                surgery_id, speciality
+            ),
+            Problem::DuplicateId {
+                surgery_id,
+                first_line: Some(first_line),
+            } => write!(
+                f,
+                "surgery id {} was already used at line {} and would be dropped",
+                surgery_id, first_line
+            ),
+            Problem::DuplicateId {
+                surgery_id,
+                first_line: None,
+            } => write!(
+                f,
+                "surgery id {} was already used and would be dropped",
+                surgery_id
+            ),
+        }
+    }
+}
+
+impl From<Problem> for SolverError {
+    fn from(problem: Problem) -> Self {
+        match problem {
+            Problem::Malformed(message) => SolverError::Parse {
+                line: 0,
+                column: None,
+                message,
+            },
+            Problem::UnknownPriority {
+                surgery_id,
+                priority,
+            } => SolverError::UnknownPriority {
+                surgery_id,
+                priority,
+            },
+            Problem::LongerThanRoomDay {
+                surgery_id,
+                duration,
+            }
+            | Problem::LongerThanSurgeonDay {
+                surgery_id,
+                duration,
+                ..
+            }
+            | Problem::LongerThanSurgeonWeek {
+                surgery_id,
+                duration,
+                ..
+            } => SolverError::SurgeryTooLong {
+                surgery_id,
+                duration,
+            },
+            Problem::NoRoomForSpeciality {
+                surgery_id,
+                speciality,
+            }
+            | Problem::NoBlockForSpeciality {
+                surgery_id,
+                speciality,
+            } => SolverError::NoRoomForSpeciality {
+                surgery_id,
+                speciality,
+            },
+            Problem::DuplicateId { surgery_id, .. } => SolverError::Parse {
+                line: 0,
+                column: None,
+                message: format!("duplicated surgery id {}", surgery_id),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// Line on the instance file, absent for instances built in memory.
+    pub line: Option<u64>,
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn check(
+        &mut self,
+        surgery: &Surgery,
+        line: Option<u64>,
+        seen_ids: &mut HashMap<usize, Option<u64>>,
+        config: &SolverConfig,
+    ) {
+        if let Some(&first_line) = seen_ids.get(&surgery.id) {
+            self.issues.push(Issue {
+                line,
+                problem: Problem::DuplicateId {
+                    surgery_id: surgery.id,
+                    first_line,
+                },
+            });
+        } else {
+            seen_ids.insert(surgery.id, line);
+        }
+
+        self.issues.extend(
+            check_surgery(surgery, config)
+                .into_iter()
+                .map(|problem| Issue { line, problem }),
+        );
+    }
+
+    /// Errors raised while deserializing a single record don't know their line, so it can be given.
+    fn push_error(&mut self, line: Option<u64>, error: SolverError) {
+        let (line, message) = match error {
+            SolverError::Parse {
+                line: error_line,
+                column,
+                message,
+            } => (
+                line.or(Some(error_line)),
+                match column {
+                    Some(column) => format!("column {}: {}", column, message),
+                    None => message,
+                },
+            ),
+            error => (line, error.to_string()),
+        };
+        self.issues.push(Issue {
+            line,
+            problem: Problem::Malformed(message),
+        });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "No problems found");
+        }
+
+        write!(f, "Found {} problem(s):", self.issues.len())?;
+        for issue in &self.issues {
+            match issue.line {
+                Some(line) => write!(f, "\nLine {}: {}", line, issue.problem)?,
+                None => write!(f, "\n{}", issue.problem)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Problems that would stop a single surgery from ever being scheduled.
+pub fn check_surgery(surgery: &Surgery, config: &SolverConfig) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if !config.priority_policy.contains(surgery.priority) {
+        problems.push(Problem::UnknownPriority {
+            surgery_id: surgery.id,
+            priority: surgery.priority,
+        });
+    }
+
+    if !RoomPerDay::fits_in_empty_room(surgery, &config.resources) {
+        problems.push(Problem::LongerThanRoomDay {
+            surgery_id: surgery.id,
+            duration: surgery.duration,
+        });
+    }
+
+    if !SurgeonDaily::new(config.resources.surgeon_max_day_time(surgery.surgeon_id))
+        .has_availability(surgery)
+    {
+        problems.push(Problem::LongerThanSurgeonDay {
+            surgery_id: surgery.id,
+            surgeon_id: surgery.surgeon_id,
+            duration: surgery.duration,
+        });
+    }
+
+    if !SurgeonWeekly::new(config.resources.surgeon_max_week_time(surgery.surgeon_id))
+        .has_availability(surgery)
+    {
+        problems.push(Problem::LongerThanSurgeonWeek {
+            surgery_id: surgery.id,
+            surgeon_id: surgery.surgeon_id,
+            duration: surgery.duration,
+        });
+    }
+
+    let resources = &config.resources;
+    if !resources.rooms.is_empty()
+        && !resources
+            .rooms
+            .iter()
+            .any(|room| room.allows(surgery.speciality))
+    {
+        problems.push(Problem::NoRoomForSpeciality {
+            surgery_id: surgery.id,
+            speciality: surgery.speciality,
+        });
+    }
+
+    if resources.has_blocks()
+        && !resources.has_block_for(surgery.speciality, config.horizon.working_days)
+    {
+        problems.push(Problem::NoBlockForSpeciality {
+            surgery_id: surgery.id,
+            speciality: surgery.speciality,
+        });
+    }
+
+    problems
+}
+
+pub fn validate_surgeries(surgeries: &[Surgery], config: &SolverConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut seen_ids = HashMap::new();
+
+    for surgery in surgeries {
+        report.check(surgery, None, &mut seen_ids, config);
+    }
+
+    report
+}
+
+pub fn validate_csv<P: AsRef<Path>>(
+    filename: P,
+    config: &SolverConfig,
+) -> Result<ValidationReport, SolverError> {
+    validate_reader(File::open(filename)?, config)
+}
+
+/// Unlike `Instance::from_reader`, keeps going after a malformed line so every problem is listed.
+pub fn validate_reader<R: Read>(
+    mut reader: R,
+    config: &SolverConfig,
+) -> Result<ValidationReport, SolverError> {
+    // Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)
+    type Record = (usize, Priority, DaysWaiting, Speciality, SurgeonID, u8);
+
+    let mut report = ValidationReport::default();
+    let mut seen_ids = HashMap::new();
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+
+    for row in csv::Reader::from_reader(content.as_slice()).records() {
+        let record = match row {
+            Ok(record) => record,
+            Err(error) => {
+                report.push_error(None, SolverError::from_csv_error(error, &content));
+                continue;
+            }
+        };
+        let line = record
+            .position()
+            .map(|position| line_at(&content, position.byte()));
+
+        match record.deserialize::<Record>(None) {
+            Ok((id, priority, days_waiting, speciality, surgeon_id, duration)) => {
+                let surgery =
+                    Surgery::new(id, duration, days_waiting, priority, speciality, surgeon_id);
+                report.check(&surgery, line, &mut seen_ids, config);
+            }
+            Err(error) => report.push_error(line, error.into()),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_of_a_crlf_file_are_reported_on_their_lines() {
+        let content = "Cirurgia (c),Prioridade (p),Dias_espera (w),Especialidade (e),Cirurgião (h),Duração (tc)\r\n\
+            1,1,10,1,1,5\r\n\
+            2,9,10,1,1,5\r\n\
+            1,2,10,1,1,5\r\n\
+            3,1,10,1,2,47\r\n\
+            4,1,ten,1,1,5\r\n\
+            5,1,10,1,1,5\r\n";
+
+        let report = validate_reader(content.as_bytes(), &SolverConfig::new()).unwrap();
+        let issue = |line, problem| Issue {
+            line: Some(line),
+            problem,
+        };
+        assert_eq!(
+            report.issues,
+            vec![
+                issue(
+                    3,
+                    Problem::UnknownPriority {
+                        surgery_id: 2,
+                        priority: 9
+                    }
+                ),
+                issue(
+                    4,
+                    Problem::DuplicateId {
+                        surgery_id: 1,
+                        first_line: Some(2)
+                    }
+                ),
+                issue(
+                    5,
+                    Problem::LongerThanRoomDay {
+                        surgery_id: 3,
+                        duration: 47
+                    }
+                ),
+                issue(
+                    5,
+                    Problem::LongerThanSurgeonDay {
+                        surgery_id: 3,
+                        surgeon_id: 2,
+                        duration: 47
+                    }
+                ),
+                issue(
+                    6,
+                    Problem::Malformed("column 3: invalid digit found in string".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -1,23 +1,147 @@
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
 
+use super::placement::{self, PlacementStrategy};
 use super::room_per_day::RoomPerDay;
+use crate::solver::error::SolverError;
+use crate::solver::resources::ResourceConfig;
+use crate::solver::rooms::{Room, RoomID};
 use crate::solver::surgeon::{SurgeonDaily, SurgeonID};
 use crate::solver::surgery::Surgery;
 
 #[derive(Debug, Clone)]
 pub struct Day {
+    // Number of the day on the whole schedule
+    number: u32,
     rooms: Vec<RoomPerDay>,
+    // Rooms that can be opened today, either in use or not
+    open_rooms: Vec<Room>,
     daily_surgeons: HashMap<SurgeonID, SurgeonDaily>,
+    resources: Arc<ResourceConfig>,
+    placement: PlacementStrategy,
 }
 
 impl Day {
-    pub fn new(rooms_count: usize, surgeon_ids: &[SurgeonID]) -> Self {
+    pub fn new(
+        day: u32,
+        rooms_count: usize,
+        surgeon_ids: &[SurgeonID],
+        resources: Arc<ResourceConfig>,
+        placement: PlacementStrategy,
+    ) -> Self {
+        // With a master schedule every room of the day is already reserved to its block
+        let (rooms, open_rooms) = if resources.has_blocks() {
+            let blocks = resources
+                .blocks_on_day(day)
+                .into_iter()
+                .map(|block| RoomPerDay::new_block(block.room, block.speciality, &resources))
+                .collect();
+            (blocks, Vec::new())
+        } else {
+            let open_rooms = resources.rooms_on_day(rooms_count, day);
+            (Vec::with_capacity(open_rooms.len()), open_rooms)
+        };
         Self {
-            rooms: Vec::with_capacity(rooms_count),
-            daily_surgeons: SurgeonDaily::many_from_ids(surgeon_ids),
+            number: day,
+            rooms,
+            open_rooms,
+            daily_surgeons: SurgeonDaily::many_from_ids(surgeon_ids, &resources, day),
+            resources,
+            placement,
         }
     }
 
+    /// First room of today that supports the surgery speciality and isn't in use yet.
+    fn free_room_for(&self, surgery: &Surgery) -> Option<RoomID> {
+        self.open_rooms
+            .iter()
+            .filter(|room| room.allows(surgery.speciality))
+            .find(|room| self.rooms.iter().all(|used| used.id() != room.id))
+            .map(|room| room.id)
+    }
+
+    /// Slots the surgery would use as the first one of a room opened for it.
+    fn new_room_time(
+        surgeon: &SurgeonDaily,
+        surgery: &Surgery,
+        resources: &ResourceConfig,
+        placement: PlacementStrategy,
+    ) -> Option<Range<u8>> {
+        let cleaning_slots = resources.cleaning_slots(surgery.speciality);
+        match placement {
+            PlacementStrategy::Append => surgeon
+                .next_schedule_time(surgery, cleaning_slots)
+                .filter(|schedule_time| {
+                    RoomPerDay::ends_on_day(schedule_time.end.into(), resources.slots_per_day)
+                }),
+            _ => placement::find_gap(
+                placement,
+                &[],
+                resources.slots_per_day,
+                u16::from(cleaning_slots) + u16::from(surgery.duration),
+                surgeon,
+            )
+            .map(|(schedule_time, _)| schedule_time),
+        }
+    }
+
+    /// Slots the surgery would use on the room, paired with the idle slots it leaves around it.
+    /// Appending goes right after the last surgery or, on an empty block room, as soon as the
+    /// surgeon can start it.
+    fn schedule_time_on(
+        room: &RoomPerDay,
+        surgeon: &SurgeonDaily,
+        surgery: &Surgery,
+        placement: PlacementStrategy,
+    ) -> Option<(Range<u8>, u8)> {
+        match placement {
+            PlacementStrategy::Append if room.is_empty() => {
+                if !room.can_schedule_surgery(surgery) {
+                    return None;
+                }
+                surgeon
+                    .next_schedule_time(surgery, room.cleaning_slots())
+                    .filter(|schedule_time| {
+                        RoomPerDay::ends_on_day(schedule_time.end.into(), room.last_slot())
+                    })
+                    .map(|schedule_time| (schedule_time, 0))
+            }
+            PlacementStrategy::Append => {
+                if !room.can_schedule_surgery(surgery) {
+                    return None;
+                }
+                let schedule_time = room.when_will_schedule(surgery)?;
+                if surgeon.can_be_allocated(&schedule_time) {
+                    Some((schedule_time, 0))
+                } else {
+                    None
+                }
+            }
+            _ => {
+                if !room.can_host(surgery) {
+                    return None;
+                }
+                let occupied = room
+                    .scheduled_surgeons()
+                    .iter()
+                    .map(|schedule| schedule.0.clone())
+                    .collect::<Vec<_>>();
+                placement::find_gap(
+                    placement,
+                    &occupied,
+                    room.last_slot(),
+                    u16::from(room.cleaning_slots()) + u16::from(surgery.duration),
+                    surgeon,
+                )
+            }
+        }
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
     pub fn rooms(&self) -> &Vec<RoomPerDay> {
         &self.rooms
     }
@@ -25,8 +149,7 @@ impl Day {
     pub fn surgeries(&self) -> Vec<Surgery> {
         self.rooms
             .iter()
-            .map(|room| room.surgeries())
-            .flatten()
+            .flat_map(|room| room.surgeries())
             .cloned()
             .collect()
     }
@@ -39,51 +162,71 @@ impl Day {
         let surgeon = &self.daily_surgeons[&surgery.surgeon_id];
 
         surgeon.has_availability(surgery)
-            && (self.rooms.len() < self.rooms.capacity()
-                || self
-                    .rooms
-                    .iter()
-                    .filter(|room| room.can_schedule_surgery(surgery))
-                    .any(|room| surgeon.can_be_allocated(&room.when_will_schedule(surgery))))
+            && ((self.free_room_for(surgery).is_some()
+                && Self::new_room_time(surgeon, surgery, &self.resources, self.placement)
+                    .is_some())
+                || self.rooms.iter().any(|room| {
+                    Self::schedule_time_on(room, surgeon, surgery, self.placement).is_some()
+                }))
     }
 
-    pub fn schedule_surgery(&mut self, surgery: Surgery) -> (usize, usize) {
+    pub fn schedule_surgery(&mut self, surgery: Surgery) -> Result<(usize, usize), SolverError> {
         debug_assert!(
             self.can_schedule_surgery(&surgery),
             "Tried to allocate a surgery on a full day"
         );
 
-        let surgeon = self.daily_surgeons.get_mut(&surgery.surgeon_id).unwrap();
+        let surgeon = &self.daily_surgeons[&surgery.surgeon_id];
+        let placement = self.placement;
+        let mut room_times = self.rooms.iter().enumerate().filter_map(|(index, room)| {
+            Self::schedule_time_on(room, surgeon, &surgery, placement)
+                .map(|(schedule_time, idle_slots)| (index, schedule_time, idle_slots))
+        });
+        let room_time = match placement {
+            PlacementStrategy::BestFit => room_times.min_by_key(|room_time| room_time.2),
+            _ => room_times.next(),
+        };
 
         // We already tested that we can schedule a surgery,
         // so if we have no room available, its because we can create a new room and schedule
         // surgery in this room
-        match self
-            .rooms
-            .iter_mut()
-            .enumerate()
-            .filter(|index_room| index_room.1.can_schedule_surgery(&surgery))
-            .map(|index_room| {
-                let to_be_scheduled = index_room.1.when_will_schedule(&surgery);
-                (index_room.0, index_room.1, to_be_scheduled)
-            })
-            .find(|index_room| surgeon.can_be_allocated(&index_room.2))
-        {
-            Some(index_room) => {
-                surgeon.allocate_by_schedule(index_room.2, surgery.clone());
-
-                (index_room.0, index_room.1.schedule_surgery(surgery))
+        match room_time {
+            Some((room_index, schedule_time, _)) => {
+                self.daily_surgeons
+                    .get_mut(&surgery.surgeon_id)
+                    .unwrap()
+                    .allocate_by_schedule(schedule_time.clone(), surgery.clone())?;
+
+                Ok((
+                    room_index,
+                    self.rooms[room_index].schedule_surgery(surgery, schedule_time),
+                ))
             }
             None => {
-                surgeon.allocate_next(surgery.clone());
-                assert!(self.rooms.len() <= self.rooms.capacity());
+                let room_id =
+                    self.free_room_for(&surgery)
+                        .ok_or(SolverError::CapacityExceeded {
+                            surgery_id: surgery.id,
+                            reason: "no room available for its speciality",
+                        })?;
+                let surgeon = self.daily_surgeons.get_mut(&surgery.surgeon_id).unwrap();
+                let schedule_time =
+                    Self::new_room_time(surgeon, &surgery, &self.resources, placement).ok_or(
+                        SolverError::CapacityExceeded {
+                            surgery_id: surgery.id,
+                            reason: "is outside surgeon availability",
+                        },
+                    )?;
+                surgeon.allocate_by_schedule(schedule_time.clone(), surgery.clone())?;
 
                 self.rooms.push(RoomPerDay::new_by_given_schedule(
+                    room_id,
                     surgery,
-                    surgeon.last_scheduled_time(),
+                    schedule_time,
+                    &self.resources,
                 ));
 
-                (self.rooms.len() - 1, 0)
+                Ok((self.rooms.len() - 1, 0))
             }
         }
     }
@@ -97,10 +240,11 @@ impl Day {
         self.daily_surgeons
             .get_mut(&surgery.surgeon_id)
             .unwrap()
-            .deallocate(&surgery);
+            .deallocate(surgery);
 
         self.rooms[room_index].unschedule_surgery(surgery_index, surgery);
-        if self.rooms[room_index].is_empty() {
+        // Blocks of the master schedule stay reserved even without surgeries
+        if self.rooms[room_index].is_empty() && !self.resources.has_blocks() {
             self.rooms.remove(room_index);
         }
     }
@@ -1,13 +1,24 @@
 pub mod day;
+pub mod placement;
 pub mod room_per_day;
 
 use day::Day;
+use placement::PlacementStrategy;
 
+use crate::solver::error::SolverError;
+use crate::solver::horizon::Horizon;
+use crate::solver::objective::saturate;
+use crate::solver::policy::PriorityPolicy;
+use crate::solver::resources::ResourceConfig;
 use crate::solver::surgeon::{SurgeonID, SurgeonWeekly};
-use crate::solver::surgery::{DaysWaiting, Priority, Surgery};
+use crate::solver::surgery::Surgery;
+use chrono::NaiveDate;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+/// Every week of a schedule, paired with its objective function value.
+pub type Schedule = Vec<(Week, f64)>;
+
 pub struct ScheduleToken {
     day_index: usize,
     room_index: usize,
@@ -16,74 +27,106 @@ pub struct ScheduleToken {
 
 #[derive(Default, Debug, Clone)]
 pub struct Week {
+    week_index: usize,
+    // Numbers of the days this week can have, skipping weekends and holidays
+    day_numbers: Vec<u32>,
     days: Vec<Day>,
-    rooms_count: usize,
     weekly_surgeons: HashMap<SurgeonID, SurgeonWeekly>,
+    resources: Arc<ResourceConfig>,
+    horizon: Arc<Horizon>,
 }
 
 impl Week {
-    pub fn new(rooms_count: usize, surgeon_ids: Arc<Vec<SurgeonID>>) -> Self {
-        Self {
-            days: Vec::with_capacity(5),
-            rooms_count,
-            weekly_surgeons: SurgeonWeekly::many_from_ids(&*surgeon_ids),
+    pub fn new(
+        week_index: usize,
+        rooms_count: usize,
+        surgeon_ids: Arc<Vec<SurgeonID>>,
+        resources: Arc<ResourceConfig>,
+        placement: PlacementStrategy,
+        horizon: Arc<Horizon>,
+    ) -> Self {
+        let day_numbers = horizon.working_days_of_week(week_index);
+        let mut week = Self {
+            week_index,
+            days: Vec::with_capacity(day_numbers.len()),
+            day_numbers,
+            weekly_surgeons: SurgeonWeekly::many_from_ids(&surgeon_ids, &resources),
+            resources,
+            horizon,
+        };
+
+        // Every working day is there from the start, so surgeries can go to any of them even when
+        // rooms or surgeons are missing on the ones before
+        for day_index in 0..week.day_numbers.len() {
+            let day = Day::new(
+                week.day_number(day_index),
+                rooms_count,
+                &surgeon_ids,
+                week.resources.clone(),
+                placement,
+            );
+            week.days.push(day);
         }
+
+        week
     }
 
     pub fn days(&self) -> &Vec<Day> {
         &self.days
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.days.iter().all(Day::is_empty)
+    }
+
+    pub fn week_index(&self) -> usize {
+        self.week_index
+    }
+
+    /// Number of the day on the whole schedule, counting weekends and holidays, see `Horizon`.
+    pub fn day_number(&self, day_index: usize) -> u32 {
+        self.day_numbers[day_index]
+    }
+
+    /// Calendar date of the day, when the horizon has a start date.
+    pub fn date(&self, day_index: usize) -> Option<NaiveDate> {
+        self.horizon.date(self.day_number(day_index))
+    }
+
     pub fn can_schedule_surgery(&self, surgery: &Surgery) -> bool {
         self.weekly_surgeons[&surgery.surgeon_id].has_availability(surgery)
-            && (self.days.len() <= self.days.capacity()
-                || self
-                    .days
-                    .iter()
-                    .any(|day| day.can_schedule_surgery(surgery)))
+            && self
+                .days
+                .iter()
+                .any(|day| day.can_schedule_surgery(surgery))
     }
 
-    pub fn schedule_surgery(&mut self, surgery: Surgery) -> ScheduleToken {
+    pub fn schedule_surgery(&mut self, surgery: Surgery) -> Result<ScheduleToken, SolverError> {
         if !self.can_schedule_surgery(&surgery) {
-            panic!("Tried to schedule a surgery on a full week!");
+            return Err(SolverError::CapacityExceeded {
+                surgery_id: surgery.id,
+                reason: "week is full",
+            });
         }
 
         self.weekly_surgeons
             .get_mut(&surgery.surgeon_id)
             .unwrap()
-            .allocate(&surgery);
+            .allocate(&surgery)?;
 
-        // We already tested that we can schedule a surgery,
-        // so if we have no day slots available, its because we can create a new day and schedule
-        // surgery in this day
-        match self
+        // We already tested that some day of the week can take the surgery
+        let day_index = self
             .days
-            .iter_mut()
-            .enumerate()
-            .find(|index_day| index_day.1.can_schedule_surgery(&surgery))
-        {
-            Some(index_day) => {
-                let (room_index, surgery_index) = index_day.1.schedule_surgery(surgery);
-                ScheduleToken {
-                    day_index: index_day.0,
-                    room_index,
-                    surgery_index,
-                }
-            }
-            None => {
-                let mut day = Day::new(
-                    self.rooms_count,
-                    &self.weekly_surgeons.keys().cloned().collect::<Vec<_>>(),
-                );
-                let (room_index, surgery_index) = day.schedule_surgery(surgery);
-                self.days.push(day);
-                ScheduleToken {
-                    day_index: self.days.len() - 1,
-                    room_index,
-                    surgery_index,
-                }
-            }
-        }
+            .iter()
+            .position(|day| day.can_schedule_surgery(&surgery))
+            .unwrap();
+        let (room_index, surgery_index) = self.days[day_index].schedule_surgery(surgery)?;
+
+        Ok(ScheduleToken {
+            day_index,
+            room_index,
+            surgery_index,
+        })
     }
 
     pub fn unschedule_surgery(&mut self, schedule_token: ScheduleToken, surgery: &Surgery) {
@@ -95,13 +138,9 @@ impl Week {
         self.weekly_surgeons
             .get_mut(&surgery.surgeon_id)
             .unwrap()
-            .deallocate(&surgery);
+            .deallocate(surgery);
 
         self.days[day_index].unschedule_surgery(room_index, surgery_index, surgery);
-
-        if self.days[day_index].is_empty() {
-            self.days.remove(day_index);
-        }
     }
 
     pub fn filter_available_surgeries(&self, surgeries: &HashSet<Surgery>) -> HashSet<Surgery> {
@@ -112,37 +151,121 @@ impl Week {
             .collect()
     }
 
-    pub fn is_full(&self, surgeries: &HashSet<Surgery>) -> bool {
-        !surgeries.is_empty() && self.filter_available_surgeries(surgeries).is_empty()
+    /// Whether none of the surgeries left fits the week, when there is any.
+    pub fn is_full<'a, I: IntoIterator<Item = &'a Surgery>>(&self, surgeries: I) -> bool {
+        let mut surgeries = surgeries.into_iter().peekable();
+        surgeries.peek().is_some() && surgeries.all(|surgery| !self.can_schedule_surgery(surgery))
     }
 
-    pub fn calculate_objective_function(
+    /// The surgeries left unscheduled are summed in the given order, which must not change
+    /// between executions, as these costs are too large for the sum to be exact.
+    pub fn calculate_objective_function<'a, I: IntoIterator<Item = &'a Surgery>>(
         &self,
-        surgeries_bin: &HashSet<Surgery>,
-        max_days_waiting: Arc<HashMap<Priority, DaysWaiting>>,
-        priority_penalties: Arc<HashMap<Priority, u32>>,
-        week_index: usize,
+        surgeries_bin: I,
+        priority_policy: Arc<PriorityPolicy>,
     ) -> f64 {
         let mut total_objective = 0.0;
 
         for (index, current_day) in self.days.iter().enumerate() {
             for surgery in current_day.surgeries() {
-                let day = index as u32 + 1 + (7 * week_index as u32);
-                total_objective +=
-                    surgery.scheduled_objective_function(max_days_waiting.clone(), day);
-                if day != 1 && surgery.priority == 1 {
-                    total_objective += surgery.penalty_for_not_scheduling_on_first_day(day);
+                let day = self.day_number(index);
+                total_objective = saturate(
+                    total_objective + surgery.scheduled_objective_function(&priority_policy, day),
+                );
+                if day != 1 {
+                    total_objective = saturate(
+                        total_objective
+                            + surgery
+                                .penalty_for_not_scheduling_on_first_day(&priority_policy, day),
+                    );
                 }
             }
         }
 
         for surgery in surgeries_bin {
-            total_objective += surgery.not_scheduled_objective_function(
-                max_days_waiting.clone(),
-                priority_penalties.clone(),
-            )
+            total_objective = saturate(
+                total_objective + surgery.not_scheduled_objective_function(&priority_policy),
+            );
         }
 
         total_objective
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::availability::DayAvailability;
+    use crate::solver::rooms::Room;
+
+    fn week(resources: ResourceConfig, surgeons_count: usize) -> Week {
+        Week::new(
+            0,
+            1,
+            Arc::new((1..=surgeons_count).collect()),
+            Arc::new(resources),
+            PlacementStrategy::Append,
+            Arc::new(Horizon::default()),
+        )
+    }
+
+    /// Days of the week with surgeries, after scheduling every surgery that fits.
+    fn used_days(week: &mut Week, surgeries: Vec<Surgery>) -> Vec<u32> {
+        for surgery in surgeries {
+            if week.can_schedule_surgery(&surgery) {
+                week.schedule_surgery(surgery).unwrap();
+            }
+        }
+
+        week.days()
+            .iter()
+            .filter(|day| !day.is_empty())
+            .map(Day::number)
+            .collect()
+    }
+
+    #[test]
+    fn surgeries_skip_a_day_with_every_room_closed() {
+        let resources = ResourceConfig {
+            rooms: vec![Room {
+                closed_days: vec![2],
+                ..Room::new(1)
+            }],
+            ..ResourceConfig::default()
+        };
+        let mut week = week(resources, 8);
+        // Two of them fill a room day
+        let surgeries = (1..=8)
+            .map(|id| Surgery::new(id, 20, 0, 1, 1, id))
+            .collect();
+
+        assert_eq!(used_days(&mut week, surgeries), vec![1, 3, 4, 5]);
+        assert_eq!(week.days()[1].rooms().len(), 0);
+    }
+
+    #[test]
+    fn surgeries_skip_a_day_off_of_their_surgeon() {
+        let resources = ResourceConfig {
+            availability: vec![DayAvailability {
+                surgeon: 1,
+                day: 2,
+                windows: Some(Vec::new()),
+                max_day_time: None,
+            }],
+            ..ResourceConfig::default()
+        };
+        let mut week = week(resources, 1);
+        // One of them fills a surgeon day
+        let surgeries = (1..=4)
+            .map(|id| Surgery::new(id, 20, 0, 1, 1, 1))
+            .collect::<HashSet<_>>();
+
+        week.schedule_surgery(Surgery::new(5, 20, 0, 1, 1, 1))
+            .unwrap();
+        assert_eq!(week.filter_available_surgeries(&surgeries), surgeries);
+        assert_eq!(
+            used_days(&mut week, surgeries.into_iter().collect()),
+            vec![1, 3, 4, 5]
+        );
+    }
+}
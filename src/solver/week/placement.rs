@@ -0,0 +1,199 @@
+use crate::solver::surgeon::SurgeonDaily;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Where a surgery goes on a room day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlacementStrategy {
+    /// After the last surgery of the room, as the original solver did.
+    #[default]
+    Append,
+    /// On the earliest idle gap of the room where the surgeon is also free.
+    FirstFit,
+    /// On the idle gap that leaves the fewest idle slots, earliest gap on ties.
+    BestFit,
+}
+
+impl FromStr for PlacementStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "append" => Ok(PlacementStrategy::Append),
+            "first_fit" => Ok(PlacementStrategy::FirstFit),
+            "best_fit" => Ok(PlacementStrategy::BestFit),
+            _ => Err(format!(
+                "unknown placement strategy {}, expected append, first_fit or best_fit",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PlacementStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementStrategy::Append => write!(f, "append"),
+            PlacementStrategy::FirstFit => write!(f, "first_fit"),
+            PlacementStrategy::BestFit => write!(f, "best_fit"),
+        }
+    }
+}
+
+/// Slots taking `length` slots on a free interval of a room already using `occupied`, where the
+/// surgeon can be allocated too, paired with the idle slots left on that interval.
+///
+/// Only meaningful for `FirstFit` and `BestFit`, `Append` never looks for gaps.
+pub fn find_gap(
+    strategy: PlacementStrategy,
+    occupied: &[Range<u8>],
+    last_slot: u8,
+    length: u16,
+    surgeon: &SurgeonDaily,
+) -> Option<(Range<u8>, u8)> {
+    let mut gaps = free_intervals(occupied, last_slot)
+        .into_iter()
+        .filter_map(|gap| {
+            let idle_slots = (gap.end - gap.start).checked_sub(length)?;
+            let schedule_time = earliest_start(gap, length, surgeon)?;
+            Some((schedule_time, idle_slots as u8))
+        });
+
+    match strategy {
+        PlacementStrategy::BestFit => gaps.min_by_key(|(_, idle_slots)| *idle_slots),
+        _ => gaps.next(),
+    }
+}
+
+/// Intervals between slot 1 and the last slot, included, not used by any of the ranges. Like the
+/// ranges, they end past their last slot, see `RoomPerDay::ends_on_day`.
+fn free_intervals(occupied: &[Range<u8>], last_slot: u8) -> Vec<Range<u16>> {
+    let mut occupied = occupied.to_vec();
+    occupied.sort_by_key(|range| range.start);
+
+    let mut intervals = Vec::new();
+    let mut free_from = 1;
+    for range in occupied {
+        let (start, end) = (u16::from(range.start), u16::from(range.end));
+        if start > free_from {
+            intervals.push(free_from..start);
+        }
+        free_from = free_from.max(end);
+    }
+    let day_end = (u16::from(last_slot) + 1).min(u16::from(u8::MAX));
+    if day_end > free_from {
+        intervals.push(free_from..day_end);
+    }
+
+    intervals
+}
+
+/// First start inside the gap where the surgeon can take `length` slots. Besides the gap start,
+/// only the slot after each surgery of the surgeon and window starts can be the earliest one.
+fn earliest_start(gap: Range<u16>, length: u16, surgeon: &SurgeonDaily) -> Option<Range<u8>> {
+    let mut starts = vec![gap.start];
+    starts.extend(
+        surgeon
+            .scheduled_times()
+            .map(|scheduled| u16::from(scheduled.end) + 1),
+    );
+    starts.extend(
+        surgeon
+            .windows()
+            .iter()
+            .map(|window| u16::from(window.start)),
+    );
+    starts.sort_unstable();
+
+    starts
+        .into_iter()
+        .filter(|&start| start >= gap.start && start + length <= gap.end)
+        .filter_map(|start| Some(u8::try_from(start).ok()?..u8::try_from(start + length).ok()?))
+        .find(|schedule_time| surgeon.can_be_allocated(schedule_time))
+}
+
+#[cfg(test)]
+// Ranges stand for occupied slots, not for the numbers they would hold
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use crate::solver::surgery::Surgery;
+
+    #[test]
+    fn free_intervals_reach_the_last_slot() {
+        assert_eq!(free_intervals(&[], 48), vec![1..49]);
+        assert_eq!(free_intervals(&[1..49], 48), vec![]);
+        assert_eq!(free_intervals(&[1..48], 48), vec![48..49]);
+        assert_eq!(free_intervals(&[250..255], 255), vec![1..250]);
+        assert_eq!(free_intervals(&[1..255], 255), vec![]);
+    }
+
+    #[test]
+    fn free_intervals_skip_overlapping_and_unsorted_ranges() {
+        assert_eq!(
+            free_intervals(&[30..40, 5..10, 8..20], 48),
+            vec![1..5, 20..30, 40..49]
+        );
+    }
+
+    #[test]
+    fn find_gap_fits_a_surgery_taking_the_whole_day() {
+        let surgeon = SurgeonDaily::new(48);
+        for strategy in [PlacementStrategy::FirstFit, PlacementStrategy::BestFit] {
+            assert_eq!(find_gap(strategy, &[], 48, 48, &surgeon), Some((1..49, 0)));
+            assert_eq!(find_gap(strategy, &[], 48, 49, &surgeon), None);
+            assert_eq!(
+                find_gap(strategy, &[], 255, 254, &SurgeonDaily::new(255)),
+                Some((1..255, 0))
+            );
+            assert_eq!(find_gap(strategy, &[], 255, 255, &surgeon), None);
+        }
+    }
+
+    #[test]
+    fn find_gap_picks_the_earliest_or_the_tightest_gap() {
+        let surgeon = SurgeonDaily::new(48);
+        let occupied = [1..5, 15..20, 24..40];
+
+        assert_eq!(
+            find_gap(PlacementStrategy::FirstFit, &occupied, 48, 4, &surgeon),
+            Some((5..9, 6))
+        );
+        assert_eq!(
+            find_gap(PlacementStrategy::BestFit, &occupied, 48, 4, &surgeon),
+            Some((20..24, 0))
+        );
+        assert_eq!(
+            find_gap(PlacementStrategy::FirstFit, &occupied, 48, 10, &surgeon),
+            Some((5..15, 0))
+        );
+        assert_eq!(
+            find_gap(PlacementStrategy::BestFit, &occupied, 48, 11, &surgeon),
+            None
+        );
+    }
+
+    #[test]
+    fn find_gap_waits_for_the_surgeon() {
+        let mut surgeon = SurgeonDaily::new(48);
+        surgeon
+            .allocate_by_schedule(1..10, Surgery::new(1, 7, 0, 1, 1, 1))
+            .unwrap();
+        assert_eq!(
+            find_gap(PlacementStrategy::FirstFit, &[], 48, 5, &surgeon),
+            Some((11..16, 43))
+        );
+
+        let surgeon = SurgeonDaily::new(48).with_windows(vec![20..30]);
+        assert_eq!(
+            find_gap(PlacementStrategy::FirstFit, &[], 48, 5, &surgeon),
+            Some((20..25, 43))
+        );
+        assert_eq!(
+            find_gap(PlacementStrategy::FirstFit, &[], 48, 11, &surgeon),
+            None
+        );
+    }
+}
@@ -1,30 +1,85 @@
+use crate::solver::resources::ResourceConfig;
+use crate::solver::rooms::RoomID;
 use crate::solver::surgeon::SurgeonID;
 use crate::solver::surgery::{Speciality, Surgery};
+use std::convert::TryFrom;
 use std::ops::Range;
 
-const LAST_SLOT: u8 = 48;
-
 #[derive(Debug, Clone)]
 pub struct RoomPerDay {
+    id: RoomID,
     surgeries: Vec<Surgery>,
     scheduled_surgeons: Vec<(Range<u8>, SurgeonID)>,
     speciality: Speciality,
-    current_used_slots: u8,
+    // Wider than slots, as configured days may be close to u8::MAX
+    current_used_slots: u16,
+    last_slot: u8,
+    cleaning_slots: u8,
 }
 
 impl RoomPerDay {
-    pub fn new_by_given_schedule(surgery: Surgery, schedule: Range<u8>) -> Self {
+    pub fn new_by_given_schedule(
+        id: RoomID,
+        surgery: Surgery,
+        schedule: Range<u8>,
+        resources: &ResourceConfig,
+    ) -> Self {
         let surgery_duration = surgery.duration;
         let surgery_speciality = surgery.speciality;
         let surgeon_id = surgery.surgeon_id;
+        let cleaning_slots = resources.cleaning_slots(surgery_speciality);
         Self {
+            id,
             surgeries: vec![surgery],
             scheduled_surgeons: vec![(schedule, surgeon_id)],
             speciality: surgery_speciality,
-            current_used_slots: 2 + surgery_duration,
+            current_used_slots: u16::from(cleaning_slots) + u16::from(surgery_duration),
+            last_slot: resources.slots_per_day,
+            cleaning_slots,
+        }
+    }
+
+    /// An empty room reserved for a speciality by the master surgical schedule.
+    pub fn new_block(id: RoomID, speciality: Speciality, resources: &ResourceConfig) -> Self {
+        Self {
+            id,
+            surgeries: Vec::new(),
+            scheduled_surgeons: Vec::new(),
+            speciality,
+            current_used_slots: 0,
+            last_slot: resources.slots_per_day,
+            cleaning_slots: resources.cleaning_slots(speciality),
         }
     }
 
+    /// Whether slots ending right before `end` are still on a room day, whose slots are numbered
+    /// from 1 to `last_slot`. Schedules end on a `u8`, so slot 255 is never used.
+    pub fn ends_on_day(end: u16, last_slot: u8) -> bool {
+        end <= u16::from(last_slot) + 1 && end <= u16::from(u8::MAX)
+    }
+
+    /// Whether the surgery fits a room day when it starts on the first slot.
+    pub fn fits_in_empty_room(surgery: &Surgery, resources: &ResourceConfig) -> bool {
+        Self::ends_on_day(
+            1 + u16::from(surgery.duration)
+                + u16::from(resources.cleaning_slots(surgery.speciality)),
+            resources.slots_per_day,
+        )
+    }
+
+    pub fn id(&self) -> RoomID {
+        self.id
+    }
+
+    /// Slots taken by surgeries and their cleaning.
+    pub fn used_slots(&self) -> u16 {
+        self.current_used_slots
+    }
+
+    pub fn cleaning_slots(&self) -> u8 {
+        self.cleaning_slots
+    }
+
     pub fn scheduled_surgeons(&self) -> &Vec<(Range<u8>, SurgeonID)> {
         &self.scheduled_surgeons
     }
@@ -37,52 +92,71 @@ impl RoomPerDay {
         &self.surgeries
     }
 
-    pub fn can_schedule_surgery(&self, surgery: &Surgery) -> bool {
-        // We need 2 time slots to clean the room and room must have the desired speciality for today
+    /// Whether the room has the surgery speciality and enough unused slots, wherever they are.
+    pub fn can_host(&self, surgery: &Surgery) -> bool {
+        // We need time slots to clean the room and room must have the desired speciality for today
         self.speciality == surgery.speciality
-            && self.current_used_slots + surgery.duration + 2 <= LAST_SLOT
+            && self.current_used_slots + self.surgery_slots(surgery) <= u16::from(self.last_slot)
+    }
+
+    /// Whether the surgery fits after the last surgery of the room.
+    pub fn can_schedule_surgery(&self, surgery: &Surgery) -> bool {
+        self.can_host(surgery)
             && self
                 .scheduled_surgeons
                 .iter()
                 .map(|schedule| schedule.0.end)
                 .max()
-                .unwrap()
-                + surgery.duration
-                + 2
-                <= LAST_SLOT
+                .is_none_or(|last_time| {
+                    Self::ends_on_day(
+                        u16::from(last_time) + self.surgery_slots(surgery),
+                        self.last_slot,
+                    )
+                })
     }
 
-    pub fn when_will_schedule(&self, surgery: &Surgery) -> Range<u8> {
-        let last_time = self.scheduled_surgeons.last().unwrap().0.end;
-        last_time..(last_time + 2 + surgery.duration)
+    pub fn last_slot(&self) -> u8 {
+        self.last_slot
     }
 
-    pub fn schedule_surgery(&mut self, surgery: Surgery) -> usize {
-        debug_assert!(
-            self.can_schedule_surgery(&surgery),
-            "Tried to allocate a surgery on a day without sufficient slots or with different speciality"
-        );
-
+    /// Slots right after the last surgery of the room, `None` when they don't fit a `u8`.
+    pub fn when_will_schedule(&self, surgery: &Surgery) -> Option<Range<u8>> {
         let last_time = self
             .scheduled_surgeons
             .iter()
             .map(|schedule| schedule.0.end)
             .max()
             .unwrap();
+        let end = u8::try_from(u16::from(last_time) + self.surgery_slots(surgery)).ok()?;
+
+        Some(last_time..end)
+    }
+
+    // Slots the surgery takes on the room, counting its cleaning
+    fn surgery_slots(&self, surgery: &Surgery) -> u16 {
+        u16::from(surgery.duration) + u16::from(self.cleaning_slots)
+    }
+
+    /// Adds the surgery on the given slots, the ones of `when_will_schedule` unless the room is
+    /// still empty or the surgery fills an idle gap.
+    pub fn schedule_surgery(&mut self, surgery: Surgery, schedule: Range<u8>) -> usize {
+        debug_assert!(
+            self.can_host(&surgery),
+            "Tried to allocate a surgery on a day without sufficient slots or with different speciality"
+        );
+
         let surgeon_id = surgery.surgeon_id;
-        let surgery_duration = surgery.duration;
 
-        self.current_used_slots += 2 + surgery.duration;
+        self.current_used_slots += self.surgery_slots(&surgery);
         self.surgeries.push(surgery);
-        self.scheduled_surgeons
-            .push((last_time..(last_time + 2 + surgery_duration), surgeon_id));
+        self.scheduled_surgeons.push((schedule, surgeon_id));
         debug_assert!(self.surgeries.len() == self.scheduled_surgeons.len());
 
         self.surgeries.len() - 1
     }
 
     pub fn unschedule_surgery(&mut self, surgery_index: usize, surgery: &Surgery) {
-        self.current_used_slots -= 2 + surgery.duration;
+        self.current_used_slots -= self.surgery_slots(surgery);
         self.surgeries.remove(surgery_index);
         self.scheduled_surgeons.remove(surgery_index);
     }